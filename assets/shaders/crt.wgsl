@@ -0,0 +1,61 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_render::globals::Globals
+
+struct CrtSettings {
+    scanlines: f32,
+    chromatic_aberration: f32,
+    barrel_distortion: f32,
+    vignette: f32,
+    grain: f32,
+    tracking_jitter: f32,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: CrtSettings;
+@group(0) @binding(3) var<uniform> globals: Globals;
+
+const PI: f32 = 3.14159265;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(screen_texture));
+    var uv = in.uv;
+
+    // VHS tracking: bands rolling down the screen shift sideways, now and then by a lot
+    let band = floor(uv.y * 24.0 + globals.time * 3.0);
+    let wobble = hash(vec2<f32>(band, floor(globals.time * 12.0))) - 0.5;
+    let tear = step(0.93, hash(vec2<f32>(band * 0.37, floor(globals.time * 4.0))));
+    uv.x += settings.tracking_jitter * wobble * (0.004 + tear * 0.03);
+
+    // barrel distortion bulges the image like a tube
+    let centered = uv * 2.0 - 1.0;
+    let bulged = centered * (1.0 + settings.barrel_distortion * 0.25 * dot(centered, centered));
+    uv = bulged * 0.5 + 0.5;
+
+    // chromatic aberration splits red and blue away from the centre
+    let split = (uv - 0.5) * settings.chromatic_aberration * 0.015;
+    let center = textureSample(screen_texture, texture_sampler, uv);
+    let red = textureSample(screen_texture, texture_sampler, uv + split).r;
+    let blue = textureSample(screen_texture, texture_sampler, uv - split).b;
+    var color = vec3<f32>(red, center.g, blue);
+
+    // dark gaps between lines every three pixels
+    let scan = 0.5 + 0.5 * cos(uv.y * size.y * 2.0 * PI / 3.0);
+    color *= mix(1.0, 0.4 + 0.6 * scan, settings.scanlines);
+
+    let vignette = smoothstep(0.4, 1.6, length(bulged));
+    color *= 1.0 - settings.vignette * vignette;
+
+    let noise = hash(in.uv * size + fract(globals.time) * 113.0) - 0.5;
+    color += vec3<f32>(noise * settings.grain * 0.25);
+
+    // the bulge pulls the borders in, keep them black like the tube's bezel
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(max(color, vec3<f32>(0.0)), center.a), inside);
+}
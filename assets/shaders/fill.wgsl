@@ -8,6 +8,8 @@
 
     mesh_view_bindings::view
 }
+#import bevy_vaporwave::fog::apply_distance_fade
+#import bevy_vaporwave::hologram::{hologram_color, hologram_offset}
 
 struct VertexOutput {
     // This is `clip position` when the struct is used as a vertex stage output
@@ -65,6 +67,9 @@ struct FillMaterial {
     shininess: f32,
     specular_strength: f32,
     use_vertex_color: i32,
+    opacity: f32,
+    fog_mode: i32,
+    hologram: f32,
 };
 
 @group(2) @binding(0)
@@ -127,7 +132,7 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
     // let displaced_position = vertex.position + vertex.normal * -10;
 
     let world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(vertex.position, 1.0));
-    out.world_position = world_position;
+    out.world_position = world_position + vec4<f32>(hologram_offset(world_position.xyz, material.hologram), 0.0);
     out.position = position_world_to_clip(out.world_position.xyz + vertex.normal * -material.displacement); 
 #endif
 
@@ -215,13 +220,16 @@ fn fragment(
     // let final_color = vec3<f32>(1.0,0.0,1.0);
     
     // Multiply with vertex color
+    var color: vec4<f32>;
     if (material.use_vertex_color == 1) {
-        return vec4<f32>(lighting_color * mesh.color.rgb, 1.0);
+        color = vec4<f32>(lighting_color * mesh.color.rgb, material.opacity);
     // }else if (material.use_vertex_color == 2) {
-    //     return vec4<f32>(lighting_color * mesh.alt_color.rgb, 1.0);
+    //     color = vec4<f32>(lighting_color * mesh.alt_color.rgb, material.opacity);
     }else{
-        return vec4<f32>(lighting_color * material.color.rgb, 1.0);
+        color = vec4<f32>(lighting_color * material.color.rgb, material.opacity);
     }
+    color = hologram_color(color, mesh.world_position.xyz, material.hologram);
+    return apply_distance_fade(color, mesh.world_position.xyz, material.fog_mode);
     
     // let final_color = lighting_color * mesh.color.rgb;
     
@@ -0,0 +1,43 @@
+#define_import_path bevy_vaporwave::fog
+
+#import bevy_pbr::{
+    mesh_view_bindings::{fog, view},
+    mesh_view_types::{
+        FOG_MODE_LINEAR, FOG_MODE_EXPONENTIAL, FOG_MODE_EXPONENTIAL_SQUARED, FOG_MODE_ATMOSPHERIC,
+    },
+}
+
+// Distance fade shared by the fill, outline and wireframe shaders, driven by the camera's
+// FogSettings. Cameras without one have fog.mode == FOG_MODE_OFF and nothing fades.
+
+const FADE_TO_FOG_COLOR: i32 = 0;
+const FADE_TO_TRANSPARENT: i32 = 1;
+
+// 0 up close, 1 fully faded, same falloffs as bevy's own fog
+fn fog_amount(world_position: vec3<f32>) -> f32 {
+    let distance = length(view.world_position - world_position);
+
+    if (fog.mode == FOG_MODE_LINEAR) {
+        let start = fog.be.x;
+        let end = fog.be.y;
+        return 1.0 - clamp((end - distance) / (end - start), 0.0, 1.0);
+    } else if (fog.mode == FOG_MODE_EXPONENTIAL) {
+        return 1.0 - 1.0 / exp(distance * fog.be.x);
+    } else if (fog.mode == FOG_MODE_EXPONENTIAL_SQUARED) {
+        let distance_times_density = distance * fog.be.x;
+        return 1.0 - 1.0 / exp(distance_times_density * distance_times_density);
+    } else if (fog.mode == FOG_MODE_ATMOSPHERIC) {
+        // no scattering for emissive lines, only the average extinction
+        let extinction = dot(fog.be, vec3<f32>(1.0 / 3.0));
+        return 1.0 - 1.0 / exp(distance * extinction);
+    }
+    return 0.0;
+}
+
+fn apply_distance_fade(color: vec4<f32>, world_position: vec3<f32>, fade_mode: i32) -> vec4<f32> {
+    let amount = fog_amount(world_position);
+    if (fade_mode == FADE_TO_TRANSPARENT) {
+        return vec4<f32>(color.rgb, color.a * (1.0 - amount));
+    }
+    return vec4<f32>(mix(color.rgb, fog.base_color.rgb, amount * fog.base_color.a), color.a);
+}
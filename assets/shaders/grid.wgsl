@@ -0,0 +1,43 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+struct GridMaterial {
+    color: vec4<f32>,
+    spacing: f32,
+    line_width: f32,
+    glow: f32,
+    brightness: f32,
+    fade_distance: f32,
+    scroll: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> material: GridMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // grid coordinates come from the world position, so the plane can follow the camera
+    let coord = (in.world_position.xz + vec2<f32>(0.0, material.scroll)) / material.spacing;
+
+    // distance to the nearest line in pixels
+    let cell = max(fwidth(coord), vec2<f32>(1e-5));
+    let to_line = abs(fract(coord - 0.5) - 0.5) / cell;
+    let d = min(to_line.x, to_line.y);
+
+    // coverage of a line_width wide line, plus a soft halo around it
+    let line = clamp(material.line_width * 0.5 + 0.5 - d, 0.0, 1.0);
+    let halo = material.glow * exp(-d / max(material.line_width * 3.0, 1e-3));
+
+    // once cells shrink to a few pixels the lines would only shimmer, so let them go
+    let detail = 1.0 - smoothstep(0.15, 0.4, max(cell.x, cell.y));
+
+    let dist = distance(in.world_position.xyz, view.world_position);
+    let fade = 1.0 - smoothstep(material.fade_distance * 0.4, material.fade_distance, dist);
+
+    let intensity = (line + halo) * detail * fade * material.color.a;
+
+    // AlphaMode::Add blends premultiplied, zero alpha keeps what is behind
+    return vec4<f32>(material.color.rgb * material.brightness * intensity, 0.0);
+}
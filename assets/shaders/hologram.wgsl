@@ -0,0 +1,39 @@
+#define_import_path bevy_vaporwave::hologram
+
+#import bevy_pbr::mesh_view_bindings::globals
+
+// Hologram and glitch look shared by the fill and wireframe shaders. `strength` is 0 for a normal
+// mesh, 1 for a steady hologram, and goes above 1 during a glitch burst.
+
+fn hologram_hash(n: f32) -> f32 {
+    return fract(sin(n * 12.9898) * 43758.5453);
+}
+
+// Sideways shift of horizontal slices of the mesh plus a small per vertex jitter, in world space.
+// Pass the undisplaced world position so every layer of a mesh moves the same way.
+fn hologram_offset(world_position: vec3<f32>, strength: f32) -> vec3<f32> {
+    // the slices change 12 times a second
+    let frame = floor(globals.time * 12.0);
+    let slice = floor(world_position.y * 8.0);
+
+    // most slices stay put, the stronger the glitch the more of them move
+    let moved = select(0.0, 1.0, hologram_hash(slice + frame * 31.0) > 1.0 - 0.1 * strength);
+    let shift = (hologram_hash(slice * 7.0 + frame) - 0.5) * 0.3 * strength * moved;
+
+    let seed = dot(world_position, vec3<f32>(1.0, 57.0, 113.0)) + frame;
+    let jitter = vec3<f32>(hologram_hash(seed), hologram_hash(seed + 1.0), hologram_hash(seed + 2.0)) - 0.5;
+
+    return vec3<f32>(shift, 0.0, 0.0) + jitter * 0.01 * strength;
+}
+
+// Scanlines scrolling up through the mesh and a flicker, they also make the mesh see-through
+fn hologram_color(color: vec4<f32>, world_position: vec3<f32>, strength: f32) -> vec4<f32> {
+    let time = globals.time;
+    let scanline = 0.5 + 0.5 * sin((world_position.y - time * 0.5) * 60.0);
+    let flicker = 1.0 - min(0.3 * strength, 0.9) * hologram_hash(floor(time * 20.0));
+
+    let amount = clamp(strength, 0.0, 1.0);
+    let light = mix(1.0, (0.4 + 0.6 * scanline) * flicker, amount);
+    let alpha = mix(1.0, 0.3 + 0.5 * scanline, amount);
+    return vec4<f32>(color.rgb * light, color.a * alpha);
+}
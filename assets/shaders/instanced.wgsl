@@ -0,0 +1,134 @@
+#import bevy_pbr::{
+    mesh_view_bindings::view,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_vaporwave::fog::apply_distance_fade
+
+// The fill, outline and wireframe layers for instancing.rs, one of FILL, OUTLINE or WIREFRAME is
+// defined. The layer settings come with each instance, see InstanceData.
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+#ifdef EDGE_TIERS
+    // parse_extras::EdgeTier, 0 primary, 1 secondary, 2 detail, plus 4 for an authored line color
+    @location(2) edge_tier: f32,
+#endif
+#ifdef VERTEX_COLORS
+    @location(5) color: vec4<f32>,
+#endif
+#ifdef OUTLINE
+    @location(8) smooth_normal: vec3<f32>,
+#endif
+
+    // x vertex color mode, y fog mode, z a bit per shown edge tier
+    @location(9) i_modes: vec4<i32>,
+    @location(10) i_world_from_local_0: vec4<f32>,
+    @location(11) i_world_from_local_1: vec4<f32>,
+    @location(12) i_world_from_local_2: vec4<f32>,
+    @location(13) i_world_from_local_3: vec4<f32>,
+    // tint, alpha includes the opacity
+    @location(14) i_color: vec4<f32>,
+    // x brightness, the rest depends on the layer
+    //   fill: displacement, shininess, specular strength
+    //   outline: width, z translate
+    //   wireframe: displacement
+    @location(15) i_params: vec4<f32>,
+    // wireframe brightness per edge tier
+    @location(3) i_tier_brightness: vec4<f32>,
+    // the material color, used unless vertex colors are
+    @location(4) i_base_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) params: vec4<f32>,
+    @location(4) @interpolate(flat) fog_mode: i32,
+#ifdef WIREFRAME
+    // x brightness, y visibility of the line's tier
+    @location(5) @interpolate(flat) tier: vec2<f32>,
+#endif
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world_from_local = mat4x4<f32>(
+        vertex.i_world_from_local_0,
+        vertex.i_world_from_local_1,
+        vertex.i_world_from_local_2,
+        vertex.i_world_from_local_3,
+    );
+    let params = vertex.i_params;
+
+    var position = vertex.position;
+#ifdef WIREFRAME
+    position += vertex.normal * (params.y * 0.15 + 0.001);
+#endif
+#ifdef OUTLINE
+    // as in outline.wgsl the width grows with the distance so it stays even on screen
+    let original_world_position = (world_from_local * vec4<f32>(vertex.position, 1.0)).xyz;
+    let distance_to_camera = length(original_world_position - view.world_position);
+    position += vertex.smooth_normal * params.y * distance_to_camera * 0.01;
+#endif
+
+    var out: VertexOutput;
+    let world_position = (world_from_local * vec4<f32>(position, 1.0)).xyz;
+    out.world_position = world_position;
+    out.world_normal = normalize((world_from_local * vec4<f32>(vertex.normal, 0.0)).xyz);
+    out.position = position_world_to_clip(world_position);
+#ifdef FILL
+    out.position = position_world_to_clip(world_position + vertex.normal * -params.y);
+#endif
+#ifdef OUTLINE
+    out.position += vec4<f32>(0.0, 0.0, -params.z, 0.0);
+#endif
+
+    // as in line.wgsl, line meshes without tiers are all secondary
+#ifdef WIREFRAME
+#ifdef EDGE_TIERS
+    let edge = u32(max(vertex.edge_tier, 0.0));
+    let tier = min(edge & 3u, 2u);
+    let authored_color = (edge & 4u) != 0u;
+#else
+    let tier = 1u;
+    let authored_color = false;
+#endif
+    let visible = (vertex.i_modes.z >> tier) & 1;
+    out.tier = vec2<f32>(vertex.i_tier_brightness[tier], f32(visible));
+#else
+    let authored_color = false;
+#endif
+
+    var base_color = vertex.i_base_color;
+#ifdef VERTEX_COLORS
+    // an authored line color wins over the material color
+    if (vertex.i_modes.x == 1 || authored_color) {
+        base_color = vertex.color;
+    }
+#endif
+    out.color = base_color * vertex.i_color;
+    out.params = params;
+    out.fog_mode = vertex.i_modes.y;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+#ifdef FILL
+    // the camera light of fill.wgsl, a black surface with a specular highlight
+    let light_dir = normalize(view.world_position - in.world_position);
+    let specular = pow(max(dot(normalize(in.world_normal), light_dir), 0.0), in.params.z) * in.params.w;
+    let color = vec4<f32>(in.color.rgb * specular * in.params.x, in.color.a);
+#else ifdef WIREFRAME
+    if (in.tier.y < 0.5) {
+        discard;
+    }
+    let color = vec4<f32>(in.color.rgb * in.params.x * in.tier.x, in.color.a);
+#else
+    let color = vec4<f32>(in.color.rgb * in.params.x, in.color.a);
+#endif
+    return apply_distance_fade(color, in.world_position, in.fog_mode);
+}
@@ -2,15 +2,60 @@
     mesh_functions,
     skinning,
     morph::morph,
-    forward_io::{Vertex, VertexOutput},
     view_transformations::position_world_to_clip,
 }
+#import bevy_vaporwave::fog::apply_distance_fade
+#import bevy_vaporwave::hologram::{hologram_color, hologram_offset}
+
+// forward_io's structs with the attributes LineMaterial::specialize binds, plus the edge tier
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+#ifdef VERTEX_POSITIONS
+    @location(0) position: vec3<f32>,
+#endif
+#ifdef VERTEX_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(5) color: vec4<f32>,
+#endif
+#ifdef SKINNED
+    @location(6) joint_indices: vec4<u32>,
+    @location(7) joint_weights: vec4<f32>,
+#endif
+#ifdef EDGE_TIERS
+    // parse_extras::EdgeTier, 0 primary, 1 secondary, 2 detail, plus 4 for an authored line color
+    // (EDGE_AUTHORED_COLOR)
+    @location(9) edge_tier: f32,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(5) color: vec4<f32>,
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    @location(6) @interpolate(flat) instance_index: u32,
+#endif
+#ifdef VISIBILITY_RANGE_DITHER
+    @location(7) @interpolate(flat) visibility_range_dither: i32,
+#endif
+    // x brightness, y visibility of the line's tier, z 1 when its color was authored
+    @location(8) @interpolate(flat) tier: vec3<f32>,
+}
 
 struct LineMaterial{
     color: vec4<f32>,
     displacement: f32,
     use_vertex_color: i32,
     brightness: f32,
+    opacity: f32,
+    fog_mode: i32,
+    hologram: f32,
+    // indexed by the edge tier
+    tier_brightness: vec4<f32>,
+    tier_visibility: vec4<f32>,
 }
 
 @group(2) @binding(0)
@@ -48,32 +93,30 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
 #endif
 
 #ifdef VERTEX_POSITIONS
+    // glitch from the undisplaced position so the lines move with the fill
+    let surface_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(vertex.position, 1.0));
+    let hologram = hologram_offset(surface_position.xyz, material.hologram);
     vertex.position = vertex.position + (vertex.normal * (material.displacement * 0.15 + 0.001));
     out.world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(vertex.position, 1.0));
+    out.world_position += vec4<f32>(hologram, 0.0);
     out.position = position_world_to_clip(out.world_position.xyz);
 #endif
 
-#ifdef VERTEX_UVS_A
-    out.uv = vertex.uv;
-#endif
-#ifdef VERTEX_UVS_B
-    out.uv_b = vertex.uv_b;
-#endif
-
-#ifdef VERTEX_TANGENTS
-    out.world_tangent = mesh_functions::mesh_tangent_local_to_world(
-        world_from_local,
-        vertex.tangent,
-        // Use vertex_no_morph.instance_index instead of vertex.instance_index to work around a wgpu dx12 bug.
-        // See https://github.com/gfx-rs/naga/issues/2416
-        vertex_no_morph.instance_index
-    );
-#endif
-
 #ifdef VERTEX_COLORS
     out.color = vertex.color;
 #endif
 
+    // line meshes without tiers are all secondary
+#ifdef EDGE_TIERS
+    let edge = u32(max(vertex.edge_tier, 0.0));
+    let tier = min(edge & 3u, 2u);
+    let authored_color = f32((edge & 4u) != 0u);
+#else
+    let tier = 1u;
+    let authored_color = 0.0;
+#endif
+    out.tier = vec3<f32>(material.tier_brightness[tier], material.tier_visibility[tier], authored_color);
+
 #ifdef VERTEX_OUTPUT_INSTANCE_INDEX
     // Use vertex_no_morph.instance_index instead of vertex.instance_index to work around a wgpu dx12 bug.
     // See https://github.com/gfx-rs/naga/issues/2416
@@ -90,12 +133,20 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (in.tier.y < 0.5) {
+        discard;
+    }
 
-    if (material.use_vertex_color == 1){
-        return in.color * material.brightness;
+    let brightness = material.brightness * in.tier.x;
+    var color: vec4<f32>;
+    // an authored line color wins over the material color
+    if (material.use_vertex_color == 1 || in.tier.z > 0.5){
+        color = vec4<f32>(in.color.rgb * brightness, in.color.a * material.opacity);
     }else{
-        return material.color * material.brightness;
+        color = vec4<f32>(material.color.rgb * brightness, material.color.a * material.opacity);
     }
+    color = hologram_color(color, in.world_position.xyz, material.hologram);
+    return apply_distance_fade(color, in.world_position.xyz, material.fog_mode);
 
     // #ifdef VERTEX_COLORS
     // return in.color;
@@ -5,6 +5,7 @@
     morph::morph,
     view_transformations::position_world_to_clip,
 }
+#import bevy_vaporwave::fog::apply_distance_fade
 
 struct Vertex {
     @builtin(instance_index) instance_index: u32,
@@ -61,6 +62,8 @@ struct OutlineMaterial {
     z_translate: f32,
     use_vertex_color: i32,
     brightness: f32,
+    opacity: f32,
+    fog_mode: i32,
 };
 
 @group(2) @binding(0)
@@ -157,5 +160,7 @@ fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color * material.brightness;
+    // brightness only scales the light, alpha has to stay 0..1 for blending
+    let color = vec4<f32>(in.color.rgb * material.brightness, in.color.a * material.opacity);
+    return apply_distance_fade(color, in.world_position.xyz, material.fog_mode);
 }
@@ -0,0 +1,85 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+struct SkyMaterial {
+    top: vec4<f32>,
+    horizon: vec4<f32>,
+    bottom: vec4<f32>,
+    sun_top: vec4<f32>,
+    sun_bottom: vec4<f32>,
+    sun_direction: vec3<f32>,
+    sun_size: f32,
+    sun_stripes: f32,
+    star_density: f32,
+    star_brightness: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> material: SkyMaterial;
+
+const PI: f32 = 3.14159265;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn gradient(dir: vec3<f32>) -> vec3<f32> {
+    if (dir.y >= 0.0) {
+        return mix(material.horizon.rgb, material.top.rgb, smoothstep(0.0, 0.6, dir.y));
+    }
+    return mix(material.horizon.rgb, material.bottom.rgb, smoothstep(0.0, 0.15, -dir.y));
+}
+
+fn stars(dir: vec3<f32>) -> f32 {
+    // cells of roughly equal size on the sphere, a star at a random spot in some of them
+    let uv = vec2<f32>(atan2(dir.z, dir.x) / PI, dir.y) * vec2<f32>(180.0, 120.0);
+    let cell = floor(uv);
+    if (hash(cell) > material.star_density) {
+        return 0.0;
+    }
+    let spot = vec2<f32>(hash(cell + 17.0), hash(cell + 31.0)) * 0.8 + 0.1;
+    let d = length(fract(uv) - spot);
+    // stars fade in above the horizon glow
+    return (1.0 - smoothstep(0.0, 0.12, d)) * hash(cell + 5.0) * smoothstep(0.05, 0.3, dir.y);
+}
+
+// 1 inside the sun disc, 0 outside and in the cut-out stripes.
+// no branches around the derivatives, they need uniform control flow
+fn sun(p: vec2<f32>) -> f32 {
+    let r = length(p);
+    let aa = fwidth(r);
+    let disc = 1.0 - smoothstep(1.0 - aa, 1.0 + aa, r);
+
+    // stripes start a little above the centre, the gaps grow towards the bottom
+    let start = 0.1;
+    let band = max(start - p.y, 0.0) / (1.0 + start) * material.sun_stripes;
+    let gap = (floor(band) + 1.0) / (material.sun_stripes + 1.0) * 0.7;
+    let edge = fwidth(band);
+    let cut = smoothstep(gap - edge, gap + edge, fract(band));
+    let striped = select(1.0, cut, material.sun_stripes > 0.0 && p.y < start);
+    return disc * striped;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dir = normalize(in.world_position.xyz - view.world_position);
+    var color = gradient(dir);
+
+    if (material.star_density > 0.0) {
+        color += vec3<f32>(stars(dir) * material.star_brightness);
+    }
+
+    // position on the sun disc, 1 at its rim, y up
+    let right = normalize(cross(vec3<f32>(0.0, 1.0, 0.0), material.sun_direction));
+    let up = cross(material.sun_direction, right);
+    let p = vec2<f32>(dot(dir, right), dot(dir, up)) / max(sin(material.sun_size), 1e-4);
+
+    // the disc projection repeats on the far side of the sky
+    let visible = select(0.0, 1.0, material.sun_size > 0.0 && dot(dir, material.sun_direction) > 0.0);
+    let sun_color = mix(material.sun_bottom.rgb, material.sun_top.rgb, clamp(p.y * 0.5 + 0.5, 0.0, 1.0));
+    color = mix(color, sun_color, sun(p) * visible);
+
+    return vec4<f32>(color, 1.0);
+}
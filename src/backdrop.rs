@@ -0,0 +1,434 @@
+use bevy::{ecs::system::SystemParam, prelude::*, render::view::NoFrustumCulling};
+use bevy_egui::egui;
+use bevy_vaporwave::{
+    mesh_ops::line_list_to_mesh,
+    terrain::{generate_terrain, Terrain, TerrainSettings},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::camera_plugin::{CameraSet, VaporwaveCamera};
+use crate::grid_material::GridMaterial;
+use crate::presets::color_srgba;
+use crate::sky_material::SkyMaterial;
+use crate::layers::{LayerSource, MeshLayers};
+
+// The scenery behind the models: the neon grid floor, the sky with its striped sun and wireframe
+// mountains. Grid and sky follow the camera around. The grid lines are placed in world space by
+// the shader so the floor looks endless, the sky only depends on the view direction.
+
+// inside the default far plane, outside anything else in the scene
+const SKY_RADIUS: f32 = 500.0;
+
+pub struct BackdropPlugin;
+
+impl Plugin for BackdropPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<GridMaterial>::default())
+            .add_plugins(MaterialPlugin::<SkyMaterial>::default())
+            .init_resource::<SkySettings>()
+            .add_systems(Startup, spawn_sky)
+            .add_systems(
+                Update,
+                (
+                    (add_grid_meshes, resize_grid_meshes, update_grids).chain(),
+                    update_sky,
+                    (add_mountains, update_mountains).chain(),
+                )
+                    .after(CameraSet),
+            );
+    }
+}
+
+/// An endless line grid in the xz plane at the entity's height. Spawn it with a `SpatialBundle`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct SynthwaveGrid {
+    // world units between lines
+    pub spacing: f32,
+    // in pixels
+    pub line_width: f32,
+    pub color: Color,
+    // same scale as the line material brightness, above 1 blooms
+    pub brightness: f32,
+    // strength of the halo around the lines
+    pub glow: f32,
+    pub fade_distance: f32,
+    // world units per second, positive moves the lines towards -z
+    pub scroll_speed: f32,
+}
+
+impl Default for SynthwaveGrid {
+    fn default() -> Self {
+        Self {
+            spacing: 1.0,
+            line_width: 1.5,
+            color: Color::srgb(1.0, 0.1, 0.8),
+            brightness: 8.0,
+            glow: 0.5,
+            fade_distance: 60.0,
+            scroll_speed: 0.0,
+        }
+    }
+}
+
+impl SynthwaveGrid {
+    fn mesh(&self) -> Mesh {
+        // reaches fade_distance in every direction from the camera
+        let size = self.fade_distance * 2.0;
+        Plane3d::default().mesh().size(size, size).build()
+    }
+}
+
+fn add_grid_meshes(
+    mut commands: Commands,
+    grids: Query<(Entity, &SynthwaveGrid), Added<SynthwaveGrid>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+) {
+    for (entity, grid) in grids.iter() {
+        commands.entity(entity).insert((
+            meshes.add(grid.mesh()),
+            materials.add(GridMaterial::default()),
+            // the plane is resized along with fade_distance, which a computed Aabb would miss
+            NoFrustumCulling,
+        ));
+    }
+}
+
+fn resize_grid_meshes(
+    grids: Query<(&SynthwaveGrid, &Handle<Mesh>), Changed<SynthwaveGrid>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (grid, mesh) in grids.iter() {
+        meshes.insert(mesh, grid.mesh());
+    }
+}
+
+fn update_grids(
+    time: Res<Time>,
+    cameras: Query<&GlobalTransform, With<VaporwaveCamera>>,
+    mut grids: Query<(&SynthwaveGrid, &mut Transform, &Handle<GridMaterial>)>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+) {
+    let camera = cameras.get_single().ok().map(|camera| camera.translation());
+
+    for (grid, mut transform, material) in grids.iter_mut() {
+        if let Some(camera) = camera {
+            transform.translation.x = camera.x;
+            transform.translation.z = camera.z;
+        }
+
+        let Some(material) = materials.get_mut(material) else {
+            continue;
+        };
+        let spacing = grid.spacing.max(0.01);
+        material.color = grid.color.to_linear().to_vec4();
+        material.spacing = spacing;
+        material.line_width = grid.line_width;
+        material.glow = grid.glow;
+        material.brightness = grid.brightness;
+        material.fade_distance = grid.fade_distance;
+        material.scroll = (material.scroll + grid.scroll_speed * time.delta_seconds()).rem_euclid(spacing);
+    }
+}
+
+/// Gradient sky with the banded retro sun, drawn instead of the clear color while enabled.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct SkySettings {
+    pub enabled: bool,
+    #[serde(with = "color_srgba")]
+    pub top: Color,
+    #[serde(with = "color_srgba")]
+    pub horizon: Color,
+    // below the horizon, where the grid fades out
+    #[serde(with = "color_srgba")]
+    pub bottom: Color,
+    #[serde(with = "color_srgba")]
+    pub sun_top: Color,
+    #[serde(with = "color_srgba")]
+    pub sun_bottom: Color,
+    // above 1 blooms
+    pub sun_brightness: f32,
+    // angular radius in degrees, 0 hides the sun
+    pub sun_size: f32,
+    // degrees above the horizon
+    pub sun_elevation: f32,
+    // degrees around the y axis, 0 is towards -z
+    pub sun_azimuth: f32,
+    pub sun_stripes: u32,
+    // fraction of the sky with a star, 0 for none
+    pub star_density: f32,
+    pub star_brightness: f32,
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            top: Color::srgb(0.02, 0.0, 0.08),
+            horizon: Color::srgb(0.45, 0.05, 0.4),
+            bottom: Color::srgb(0.02, 0.0, 0.04),
+            sun_top: Color::srgb(1.0, 0.85, 0.2),
+            sun_bottom: Color::srgb(1.0, 0.1, 0.55),
+            sun_brightness: 4.0,
+            sun_size: 12.0,
+            sun_elevation: 6.0,
+            sun_azimuth: 0.0,
+            sun_stripes: 7,
+            star_density: 0.05,
+            star_brightness: 2.0,
+        }
+    }
+}
+
+impl SkySettings {
+    fn sun_direction(&self) -> Vec3 {
+        let rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            -self.sun_azimuth.to_radians(),
+            self.sun_elevation.to_radians(),
+            0.0,
+        );
+        rotation * Vec3::NEG_Z
+    }
+}
+
+#[derive(Component)]
+struct Sky;
+
+fn spawn_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+) {
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(Sphere::new(SKY_RADIUS).mesh().uv(32, 18)),
+            material: materials.add(SkyMaterial::default()),
+            ..default()
+        },
+        Sky,
+    ));
+}
+
+fn update_sky(
+    settings: Res<SkySettings>,
+    cameras: Query<&GlobalTransform, With<VaporwaveCamera>>,
+    mut skies: Query<(&mut Transform, &mut Visibility, &Handle<SkyMaterial>), With<Sky>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+) {
+    let camera = cameras.get_single().ok().map(|camera| camera.translation());
+
+    for (mut transform, mut visibility, material) in skies.iter_mut() {
+        if let Some(camera) = camera {
+            transform.translation = camera;
+        }
+
+        if !settings.is_changed() {
+            continue;
+        }
+        visibility.set_if_neq(if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        let Some(material) = materials.get_mut(material) else {
+            continue;
+        };
+        let sun = |color: Color| (color.to_linear() * settings.sun_brightness).to_vec4();
+        *material = SkyMaterial {
+            top: settings.top.to_linear().to_vec4(),
+            horizon: settings.horizon.to_linear().to_vec4(),
+            bottom: settings.bottom.to_linear().to_vec4(),
+            sun_top: sun(settings.sun_top),
+            sun_bottom: sun(settings.sun_bottom),
+            sun_direction: settings.sun_direction(),
+            sun_size: settings.sun_size.to_radians(),
+            sun_stripes: settings.sun_stripes as f32,
+            star_density: settings.star_density,
+            star_brightness: settings.star_brightness,
+        };
+    }
+}
+
+/// Generated mountain range, drawn with the fill, outline and wireframe materials like the models.
+/// Add `VaporwaveLayers` next to it to leave some of them out.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct Mountains {
+    pub terrain: TerrainSettings,
+    // world units per second, positive moves the mountains towards -z like the grid
+    pub scroll_speed: f32,
+}
+
+// meshes shared by the material layers, and how far the range has scrolled
+#[derive(Component)]
+struct MountainMeshes {
+    surface: Handle<Mesh>,
+    lines: Handle<Mesh>,
+    scroll: f32,
+    // whole cells the heightfield has been regenerated at
+    step: i64,
+}
+
+// the surface and line mesh entities, moved by the part of the scroll that is less than a cell
+#[derive(Component)]
+struct MountainLayer;
+
+fn terrain_meshes(mountains: &Mountains, step: i64) -> Option<(Mesh, Mesh)> {
+    let scroll = step as f32 * mountains.terrain.cell_size().y;
+    match generate_terrain(&mountains.terrain, scroll) {
+        Ok(Terrain { mesh, line_list }) => {
+            let lines = line_list_to_mesh(&line_list, &mesh);
+            Some((mesh, lines))
+        }
+        Err(e) => {
+            warn!("unable to generate terrain: {}", e);
+            None
+        }
+    }
+}
+
+fn add_mountains(
+    mut commands: Commands,
+    mountains: Query<(Entity, &Mountains), Added<Mountains>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, mountains) in mountains.iter() {
+        let Some((surface, lines)) = terrain_meshes(mountains, 0) else {
+            continue;
+        };
+        let surface = meshes.add(surface);
+        let lines = meshes.add(lines);
+
+        // laid out like a baked model, sync_layers gives the surface its fill and outline and the
+        // line mesh its wireframe, material settings come from the controls window like everything else
+        commands
+            .entity(entity)
+            .insert(MountainMeshes {
+                surface: surface.clone(),
+                lines: lines.clone(),
+                scroll: 0.0,
+                step: 0,
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    SpatialBundle::default(),
+                    surface,
+                    MeshLayers::new(LayerSource::Baked),
+                    MountainLayer,
+                ));
+                parent.spawn((
+                    SpatialBundle::default(),
+                    lines,
+                    MeshLayers::new(LayerSource::Lines),
+                    MountainLayer,
+                ));
+            });
+    }
+}
+
+fn update_mountains(
+    time: Res<Time>,
+    mut mountains: Query<(Ref<Mountains>, &mut MountainMeshes, &Children)>,
+    mut layers: Query<&mut Transform, With<MountainLayer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (settings, mut state, children) in mountains.iter_mut() {
+        let cell = settings.terrain.cell_size().y;
+        state.scroll += settings.scroll_speed * time.delta_seconds();
+        let step = (state.scroll / cell).floor() as i64;
+
+        if settings.is_changed() || step != state.step {
+            if let Some((surface, lines)) = terrain_meshes(&settings, step) {
+                meshes.insert(&state.surface, surface);
+                meshes.insert(&state.lines, lines);
+            }
+            state.step = step;
+        }
+
+        let offset = state.scroll - step as f32 * cell;
+        for &child in children.iter() {
+            if let Ok(mut transform) = layers.get_mut(child) {
+                transform.translation.z = -offset;
+            }
+        }
+    }
+}
+
+pub fn color_edit(ui: &mut egui::Ui, label: &str, color: &mut Color) {
+    let mut rgba = color.to_srgba().to_f32_array();
+    ui.horizontal(|ui| {
+        ui.label(label);
+        if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+            *color = Srgba::from_f32_array(rgba).into();
+        }
+    });
+}
+
+/// Sky, grid and mountain controls for the controls window.
+#[derive(SystemParam)]
+pub struct BackdropEditor<'w, 's> {
+    grids: Query<'w, 's, &'static mut SynthwaveGrid>,
+    mountains: Query<'w, 's, &'static mut Mountains>,
+    sky: ResMut<'w, SkySettings>,
+}
+
+impl BackdropEditor<'_, '_> {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Sky");
+
+        let mut sky = *self.sky;
+        ui.checkbox(&mut sky.enabled, "Show Sky");
+        color_edit(ui, "Top", &mut sky.top);
+        color_edit(ui, "Horizon", &mut sky.horizon);
+        color_edit(ui, "Below Horizon", &mut sky.bottom);
+        color_edit(ui, "Sun Top", &mut sky.sun_top);
+        color_edit(ui, "Sun Bottom", &mut sky.sun_bottom);
+        ui.add(egui::Slider::new(&mut sky.sun_brightness, 0.0..=20.0).text("Sun Brightness"));
+        ui.add(egui::Slider::new(&mut sky.sun_size, 0.0..=45.0).text("Sun Size"));
+        ui.add(egui::Slider::new(&mut sky.sun_elevation, -20.0..=80.0).text("Sun Elevation"));
+        ui.add(egui::Slider::new(&mut sky.sun_azimuth, -180.0..=180.0).text("Sun Azimuth"));
+        ui.add(egui::Slider::new(&mut sky.sun_stripes, 0..=16).text("Sun Stripes"));
+        ui.add(egui::Slider::new(&mut sky.star_density, 0.0..=0.3).text("Star Density"));
+        ui.add(egui::Slider::new(&mut sky.star_brightness, 0.0..=10.0).text("Star Brightness"));
+        self.sky.set_if_neq(sky);
+
+        ui.separator();
+
+        for mut grid in self.grids.iter_mut() {
+            ui.heading("Grid");
+
+            let mut settings = *grid;
+            ui.add(egui::Slider::new(&mut settings.spacing, 0.1..=10.0).text("Spacing"));
+            ui.add(egui::Slider::new(&mut settings.line_width, 0.5..=8.0).text("Line Width"));
+            ui.add(egui::Slider::new(&mut settings.brightness, 0.0..=30.0).text("Grid Brightness"));
+            ui.add(egui::Slider::new(&mut settings.glow, 0.0..=2.0).text("Grid Glow"));
+            ui.add(egui::Slider::new(&mut settings.fade_distance, 5.0..=300.0).text("Fade Distance"));
+            ui.add(egui::Slider::new(&mut settings.scroll_speed, -20.0..=20.0).text("Scroll Speed"));
+
+            color_edit(ui, "Grid Color", &mut settings.color);
+
+            grid.set_if_neq(settings);
+        }
+
+        for mut mountains in self.mountains.iter_mut() {
+            ui.separator();
+            ui.heading("Mountains");
+
+            let mut settings = mountains.clone();
+            let terrain = &mut settings.terrain;
+            ui.add(egui::Slider::new(&mut terrain.height, 0.0..=50.0).text("Height"));
+            ui.add(egui::Slider::new(&mut terrain.valley_width, 0.0..=100.0).text("Valley Width"));
+            ui.add(egui::Slider::new(&mut terrain.feature_size, 1.0..=50.0).text("Feature Size"));
+            ui.add(egui::Slider::new(&mut terrain.resolution[0], 1..=200).text("Cells Across"));
+            ui.add(egui::Slider::new(&mut terrain.resolution[1], 1..=200).text("Cells Deep"));
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut terrain.seed));
+            });
+            ui.add(egui::Slider::new(&mut settings.scroll_speed, -20.0..=20.0).text("Mountain Scroll Speed"));
+
+            mountains.set_if_neq(settings);
+        }
+    }
+}
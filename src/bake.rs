@@ -0,0 +1,305 @@
+// Offline counterpart of `post_process`: runs the same mesh_ops pipeline on a glTF file and writes
+// the results back, so the runtime can load the scene without processing it.
+//
+// Every triangle primitive is replaced by its de-indexed, flat shaded version with a
+// `_SMOOTH_NORMAL` attribute for the outline, is marked with `BAKED_EXTRA`, and gains a sibling
+// `LINES` primitive holding its wireframe. All buffers are merged into a single `.bin` next to the output file.
+//
+// The `_VERT_INDEX` layers are left out, the edges have already been resolved into the `LINES`
+// primitive, and glTF doesn't allow the unsigned int accessors they would need.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use bevy::render::{
+    mesh::{Mesh, MeshVertexAttributeId, VertexAttributeValues},
+    render_resource::VertexFormat,
+};
+use gltf::json::{
+    self,
+    accessor::{ComponentType, GenericComponentType, Type},
+    buffer::Target,
+    mesh::{Mode, Primitive, Semantic},
+    validation::{Checked, USize64},
+    Index,
+};
+
+use crate::gltf_import::{load_gltf, mesh_node_extras, primitive_to_mesh};
+use crate::mesh_ops::{
+    ensure_vertex_colors, line_list_to_mesh, mesh_seed, prepare_vaporwave_mesh, ColorizeStrategy,
+    MeshToLineList,
+};
+use crate::parse_extras::line_list_from_node_extras;
+use crate::{ATTRIBUTE_EDGE_TIER, ATTRIBUTE_SMOOTHED_NORMAL};
+
+/// Key of the primitive extra that marks the triangle primitives `bake_gltf` wrote. bevy hands
+/// primitive extras to the mesh entity as `GltfExtras`, which is how `post_process` tells them apart.
+pub const BAKED_EXTRA: &str = "vaporwave_baked";
+
+/// Whether primitive extras JSON, as bevy exposes it in `GltfExtras::value`, carries `BAKED_EXTRA`.
+pub fn is_baked_primitive(extras: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(extras)
+        .ok()
+        .and_then(|value| value.get(BAKED_EXTRA)?.as_bool())
+        .unwrap_or(false)
+}
+
+// Adds BAKED_EXTRA to a primitive's extras, keeping whatever the exporter put there
+fn mark_baked(extras: &json::Extras) -> Result<json::Extras> {
+    let mut value = match extras {
+        Some(raw) => serde_json::from_str(raw.get())?,
+        None => serde_json::Value::Object(Default::default()),
+    };
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("primitive extras aren't an object"))?;
+    object.insert(BAKED_EXTRA.to_string(), true.into());
+    Ok(Some(json::extras::RawValue::from_string(value.to_string())?))
+}
+
+#[derive(Debug, Default)]
+pub struct BakeSummary {
+    pub primitives: usize,
+    pub lines: usize,
+    pub skipped: Vec<String>,
+}
+
+/// The path the runtime loads `input` from, `gltf/astro.gltf` for `.../assets/gltf/astro.gltf`.
+/// Random colors are seeded from it like `post_process` does, so baked and runtime colors match.
+/// Files outside an `assets` directory keep the path they were given.
+pub fn default_asset_path(input: &Path) -> String {
+    let absolute = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+    let components: Vec<_> = absolute.components().collect();
+    let relative = match components.iter().rposition(|c| c.as_os_str() == "assets") {
+        Some(assets) => components[assets + 1..].iter().collect::<PathBuf>(),
+        None => input.to_path_buf(),
+    };
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Bakes `input` into `output`, `asset_path` is what the runtime would load `input` as, see
+/// `default_asset_path`.
+pub fn bake_gltf(input: &Path, output: &Path, asset_path: &str) -> Result<BakeSummary> {
+    let source = load_gltf(input)?;
+    let mut root = source.document.clone().into_json();
+    let mut bin = BinWriter::default();
+    let mut summary = BakeSummary::default();
+
+    bin.merge_buffers(&mut root, &source.buffers);
+
+    for mesh in source.document.meshes() {
+        let edge_data = mesh_node_extras(&source.document, &mesh).and_then(line_list_from_node_extras);
+
+        for primitive in mesh.primitives() {
+            let label = format!(
+                "mesh {} ({}) primitive {}",
+                mesh.index(),
+                mesh.name().unwrap_or("unnamed"),
+                primitive.index()
+            );
+
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                summary.skipped.push(format!("{}: not a triangle list", label));
+                continue;
+            }
+            if primitive.morph_targets().next().is_some() {
+                summary.skipped.push(format!("{}: morph targets can't be de-indexed", label));
+                continue;
+            }
+
+            let mut bevy_mesh = primitive_to_mesh(&primitive, &source.buffers)?;
+            if bevy_mesh.contains_attribute(ATTRIBUTE_SMOOTHED_NORMAL) {
+                summary.skipped.push(format!("{}: already baked", label));
+                continue;
+            }
+
+            prepare_vaporwave_mesh(&mut bevy_mesh).map_err(|e| anyhow!("{}: {}", label, e))?;
+            // same fallback post_process uses for scenes without a Colorize
+            let seed = mesh_seed(&format!("{}#Mesh{}", asset_path, mesh.index()), primitive.index());
+            ensure_vertex_colors(&mut bevy_mesh, &ColorizeStrategy::default(), seed)
+                .map_err(|e| anyhow!("{}: {}", label, e))?;
+
+            let line_list = match &edge_data {
+                Some(p) => bevy_mesh.mesh_to_line_list_from_json(p),
+                None => bevy_mesh.mesh_to_line_list(),
+            };
+
+            let attributes = bin.push_mesh(&mut root, &bevy_mesh)?;
+            let json_primitive = &mut root.meshes[mesh.index()].primitives[primitive.index()];
+            json_primitive.attributes = attributes;
+            json_primitive.indices = None;
+            json_primitive.extras = mark_baked(&json_primitive.extras).map_err(|e| anyhow!("{}: {}", label, e))?;
+            summary.primitives += 1;
+
+            if line_list.lines.is_empty() {
+                summary.skipped.push(format!("{}: no edges", label));
+                continue;
+            }
+
+            let line_mesh = line_list_to_mesh(&line_list, &bevy_mesh);
+            let line_attributes = bin.push_mesh(&mut root, &line_mesh)?;
+            // the wireframe shares its triangles' material so other tools show it alike
+            let material = root.meshes[mesh.index()].primitives[primitive.index()].material;
+            root.meshes[mesh.index()].primitives.push(Primitive {
+                attributes: line_attributes,
+                extensions: None,
+                extras: Default::default(),
+                indices: None,
+                material,
+                mode: Checked::Valid(Mode::Lines),
+                targets: None,
+            });
+            summary.lines += line_list.lines.len();
+        }
+    }
+
+    bin.finish(&mut root, output)?;
+    Ok(summary)
+}
+
+// glTF semantics for the mesh attributes the runtime reads back
+fn baked_semantics() -> Vec<(MeshVertexAttributeId, Semantic)> {
+    vec![
+        (Mesh::ATTRIBUTE_POSITION.id, Semantic::Positions),
+        (Mesh::ATTRIBUTE_NORMAL.id, Semantic::Normals),
+        (Mesh::ATTRIBUTE_COLOR.id, Semantic::Colors(0)),
+        (Mesh::ATTRIBUTE_UV_0.id, Semantic::TexCoords(0)),
+        (Mesh::ATTRIBUTE_JOINT_INDEX.id, Semantic::Joints(0)),
+        (Mesh::ATTRIBUTE_JOINT_WEIGHT.id, Semantic::Weights(0)),
+        (ATTRIBUTE_SMOOTHED_NORMAL.id, Semantic::Extras("SMOOTH_NORMAL".to_string())),
+        (ATTRIBUTE_EDGE_TIER.id, Semantic::Extras("EDGE_TIER".to_string())),
+    ]
+}
+
+#[derive(Default)]
+struct BinWriter {
+    data: Vec<u8>,
+}
+
+impl BinWriter {
+    fn align(&mut self) {
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+    }
+
+    // Moves every existing buffer into the single output buffer and repoints the views
+    fn merge_buffers(&mut self, root: &mut json::Root, buffers: &[gltf::buffer::Data]) {
+        let offsets: Vec<u64> = buffers
+            .iter()
+            .map(|buffer| {
+                self.align();
+                let offset = self.data.len() as u64;
+                self.data.extend_from_slice(&buffer.0);
+                offset
+            })
+            .collect();
+
+        for view in root.buffer_views.iter_mut() {
+            let offset = view.byte_offset.map_or(0, |o| o.0) + offsets[view.buffer.value()];
+            view.byte_offset = Some(USize64(offset));
+            view.buffer = Index::new(0);
+        }
+    }
+
+    fn push_mesh(
+        &mut self,
+        root: &mut json::Root,
+        mesh: &Mesh,
+    ) -> Result<std::collections::BTreeMap<Checked<Semantic>, Index<json::Accessor>>> {
+        let mut attributes = std::collections::BTreeMap::new();
+        for (id, semantic) in baked_semantics() {
+            if let Some(values) = mesh.attribute(id) {
+                let accessor = self.push_accessor(root, values, semantic == Semantic::Positions)?;
+                attributes.insert(Checked::Valid(semantic), accessor);
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn push_accessor(
+        &mut self,
+        root: &mut json::Root,
+        values: &VertexAttributeValues,
+        with_bounds: bool,
+    ) -> Result<Index<json::Accessor>> {
+        let (component_type, type_) = match values {
+            VertexAttributeValues::Float32(_) => (ComponentType::F32, Type::Scalar),
+            VertexAttributeValues::Float32x2(_) => (ComponentType::F32, Type::Vec2),
+            VertexAttributeValues::Float32x3(_) => (ComponentType::F32, Type::Vec3),
+            VertexAttributeValues::Float32x4(_) => (ComponentType::F32, Type::Vec4),
+            VertexAttributeValues::Uint16x4(_) => (ComponentType::U16, Type::Vec4),
+            other => return Err(anyhow!("can't bake {:?} attributes", VertexFormat::from(other))),
+        };
+
+        // glTF requires bounds on POSITION
+        let (min, max) = match values {
+            VertexAttributeValues::Float32x3(positions) if with_bounds => {
+                let mut min = [f32::MAX; 3];
+                let mut max = [f32::MIN; 3];
+                for position in positions {
+                    for axis in 0..3 {
+                        min[axis] = min[axis].min(position[axis]);
+                        max[axis] = max[axis].max(position[axis]);
+                    }
+                }
+                (Some(serde_json::json!(min)), Some(serde_json::json!(max)))
+            }
+            _ => (None, None),
+        };
+
+        self.align();
+        let offset = self.data.len();
+        self.data.extend_from_slice(values.get_bytes());
+
+        let view = root.push(json::buffer::View {
+            buffer: Index::new(0),
+            byte_length: USize64::from(values.get_bytes().len()),
+            byte_offset: Some(USize64::from(offset)),
+            byte_stride: None,
+            name: None,
+            target: Some(Checked::Valid(Target::ArrayBuffer)),
+            extensions: None,
+            extras: Default::default(),
+        });
+
+        Ok(root.push(json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: None,
+            count: USize64::from(values.len()),
+            component_type: Checked::Valid(GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Checked::Valid(type_),
+            min,
+            max,
+            name: None,
+            normalized: false,
+            sparse: None,
+        }))
+    }
+
+    fn finish(mut self, root: &mut json::Root, output: &Path) -> Result<()> {
+        self.align();
+
+        let bin_path = output.with_extension("bin");
+        let bin_name = bin_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("invalid output path {}", output.display()))?
+            .to_string();
+
+        root.buffers = vec![json::Buffer {
+            byte_length: USize64::from(self.data.len()),
+            name: None,
+            uri: Some(bin_name),
+            extensions: None,
+            extras: Default::default(),
+        }];
+        root.asset.generator = Some("vaporwave-bake".to_string());
+
+        std::fs::write(&bin_path, &self.data)?;
+        std::fs::write(output, root.to_string_pretty()?)?;
+        Ok(())
+    }
+}
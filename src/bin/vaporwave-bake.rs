@@ -0,0 +1,40 @@
+// Bakes the wireframe and outline data of a glTF ahead of time, so the runtime loads it
+// with zero processing. Runs headless, no window or GPU required.
+//
+// usage: vaporwave-bake <input.gltf|input.glb> <output.gltf> [asset path]
+//
+// The asset path, `gltf/astro.gltf`, seeds the generated vertex colors the way the runtime does.
+// It defaults to the input's path below its `assets` directory.
+
+use std::{path::Path, process::ExitCode};
+
+use bevy_vaporwave::bake::{bake_gltf, default_asset_path};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (input, output, asset_path) = match args.as_slice() {
+        [input, output] => (input, output, default_asset_path(Path::new(input))),
+        [input, output, asset_path] => (input, output, asset_path.clone()),
+        _ => {
+            eprintln!("usage: vaporwave-bake <input.gltf|input.glb> <output.gltf> [asset path]");
+            return ExitCode::from(2);
+        }
+    };
+
+    match bake_gltf(Path::new(input), Path::new(output), &asset_path) {
+        Ok(summary) => {
+            for skipped in &summary.skipped {
+                println!("skipped {}", skipped);
+            }
+            println!(
+                "baked {} primitives, {} lines -> {}",
+                summary.primitives, summary.lines, output
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("vaporwave-bake: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Reports what the runtime will make of a glTF, per mesh and primitive: attributes, edge extras
+// and their format, edges that don't resolve against `_VERT_INDEX`, vertex colors and skinning.
+// Exits non-zero when any primitive has errors, so it can gate asset CI.
+//
+// usage: vaporwave-inspect [--json] <file.gltf|file.glb>...
+
+use std::{path::Path, process::ExitCode};
+
+use bevy_vaporwave::inspect::{inspect_gltf, InspectReport};
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("vaporwave-inspect: unknown option {}", arg);
+                return ExitCode::from(2);
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("usage: vaporwave-inspect [--json] <file.gltf|file.glb>...");
+        return ExitCode::from(2);
+    }
+
+    let mut reports = Vec::new();
+    let mut failed = false;
+    for file in &files {
+        let report = inspect_gltf(Path::new(file)).unwrap_or_else(|e| InspectReport::failed(file, &e));
+        failed |= report.error_count() > 0;
+        reports.push(report);
+    }
+
+    if json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("vaporwave-inspect: {:#}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        for report in &reports {
+            print_report(report);
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_report(report: &InspectReport) {
+    if let Some(error) = &report.error {
+        println!("{}: error: {}", report.file, error);
+        return;
+    }
+
+    println!(
+        "{} ({})",
+        report.file,
+        report.generator.as_deref().unwrap_or("unknown generator")
+    );
+
+    for primitive in &report.primitives {
+        println!(
+            "  mesh {} ({}) primitive {}: {}, {} vertices{}",
+            primitive.mesh,
+            primitive.mesh_name.as_deref().unwrap_or("unnamed"),
+            primitive.primitive,
+            primitive.mode,
+            primitive.vertices,
+            if primitive.baked { ", baked" } else { "" }
+        );
+        println!("    attributes: {}", primitive.attributes.join(", "));
+        if !primitive.vert_index_layers.is_empty() {
+            println!("    index layers: {:?}", primitive.vert_index_layers);
+        }
+        for edges in &primitive.edges {
+            println!(
+                "    edges: {:?}, {} sets, {} edges, {} unresolved",
+                edges.format, edges.edge_sets, edges.edges, edges.unresolved
+            );
+            if !edges.unresolved_samples.is_empty() {
+                println!("      e.g. {:?}", edges.unresolved_samples);
+            }
+        }
+        if let Some(skinning) = &primitive.skinning {
+            println!(
+                "    skin: {} ({} joints), JOINTS_0: {}, WEIGHTS_0: {}",
+                skinning.skin.as_deref().unwrap_or("unnamed"),
+                skinning.joints,
+                skinning.has_joint_indices,
+                skinning.has_joint_weights
+            );
+        }
+        for warning in &primitive.warnings {
+            println!("    warning: {}", warning);
+        }
+        for error in &primitive.errors {
+            println!("    error: {}", error);
+        }
+    }
+
+    println!(
+        "  {} errors, {} warnings",
+        report.error_count(),
+        report.warning_count()
+    );
+}
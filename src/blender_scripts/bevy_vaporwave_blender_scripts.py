@@ -1,8 +1,37 @@
 import bpy
 import bmesh
 import json
+from bpy.props import EnumProperty, FloatVectorProperty
 from bpy.types import Panel, Operator
 
+# '_EDGE_TIER' values, 0 leaves an edge untagged (secondary), see EdgeTier in parse_extras.rs
+EDGE_TIERS = {1: "primary", 2: "secondary", 3: "detail"}
+
+# '_EDGE_COLOR' holds a linear RGBA color packed into 8 bits for red, green and blue and 7 for
+# alpha, the lowest bit is set for edges that have a color so even transparent black is one
+EDGE_COLOR_SET = 1
+
+def pack_edge_color(color):
+    r, g, b = (max(0, min(255, round(c * 255))) for c in color[:3])
+    a = max(0, min(127, round(color[3] * 127)))
+    value = (r << 24) | (g << 16) | (b << 8) | (a << 1) | EDGE_COLOR_SET
+    return value - (1 << 32) if value >= (1 << 31) else value
+
+def unpack_edge_color(value):
+    if not value & EDGE_COLOR_SET:
+        return None
+    value &= 0xFFFFFFFF
+    return [((value >> shift) & 0xFF) / 255 for shift in (24, 16, 8)] + [((value >> 1) & 0x7F) / 127]
+
+# ids are exported as floats, which are only exact up to 2^24, so '_VERT_INDEX' holds the low 24
+# bits and '_VERT_INDEX_HI' the rest, see vert_indices in mesh_ops.rs. They start at 1, vertices
+# joined in from another object read 0 in this object's layers, which leaves them unset
+LOW_ID_BITS = 24
+
+def vert_id(vert, low_layer, high_layer):
+    high = vert[high_layer] if high_layer is not None else 0
+    return (high << LOW_ID_BITS) | vert[low_layer]
+
 def add_indices():
     selected_objects = bpy.context.selected_objects
 
@@ -27,10 +56,13 @@ def add_indices():
 
         # Create a new custom attribute layer for vertex indices
         vert_index_layer = bm.verts.layers.int.new('_VERT_INDEX')
+        vert_index_hi_layer = bm.verts.layers.int.new('_VERT_INDEX_HI')
 
         # Iterate through all vertices and assign unique IDs
-        for idx, v in enumerate(bm.verts):
-            v[vert_index_layer] = idx
+        low_mask = (1 << LOW_ID_BITS) - 1
+        for idx, v in enumerate(bm.verts, start=1):
+            v[vert_index_layer] = idx & low_mask
+            v[vert_index_hi_layer] = idx >> LOW_ID_BITS
 
         # Update the mesh with BMesh data
         bm.to_mesh(obj.data)
@@ -154,6 +186,159 @@ def clear_visible_edges(obj):
     if was_in_edit_mode:
         bpy.ops.object.mode_set(mode='EDIT')
 
+def set_edge_tier(obj, tier):
+    if obj.type != 'MESH':
+        return
+
+    was_in_edit_mode = (obj.mode == 'EDIT')
+    if was_in_edit_mode:
+        bpy.ops.object.mode_set(mode='OBJECT')
+
+    bm = bmesh.new()
+    bm.from_mesh(obj.data)
+
+    tier_layer = bm.edges.layers.int.get('_EDGE_TIER')
+    if tier_layer is None:
+        tier_layer = bm.edges.layers.int.new('_EDGE_TIER')
+
+    for edge in bm.edges:
+        if edge.select:
+            edge[tier_layer] = tier
+
+    bm.to_mesh(obj.data)
+    bm.free()
+
+    if was_in_edit_mode:
+        bpy.ops.object.mode_set(mode='EDIT')
+
+def set_edge_color(obj, color):
+    # color None clears it, the line goes back to the vertex colors
+    if obj.type != 'MESH':
+        return
+
+    was_in_edit_mode = (obj.mode == 'EDIT')
+    if was_in_edit_mode:
+        bpy.ops.object.mode_set(mode='OBJECT')
+
+    bm = bmesh.new()
+    bm.from_mesh(obj.data)
+
+    color_layer = bm.edges.layers.int.get('_EDGE_COLOR')
+    if color_layer is None:
+        color_layer = bm.edges.layers.int.new('_EDGE_COLOR')
+
+    value = 0 if color is None else pack_edge_color(color)
+    for edge in bm.edges:
+        if edge.select:
+            edge[color_layer] = value
+
+    bm.to_mesh(obj.data)
+    bm.free()
+
+    if was_in_edit_mode:
+        bpy.ops.object.mode_set(mode='EDIT')
+
+def get_index_layers(bm):
+    # '_VERT_INDEX' is layer 0, joined objects keep theirs as '_VERT_INDEX.00N', each with its
+    # '_VERT_INDEX_HI' counterpart unless it was made before those existed
+    int_layers = bm.verts.layers.int
+    layers = []
+    for name, layer in int_layers.items():
+        if name == '_VERT_INDEX':
+            layers.append((0, layer, int_layers.get('_VERT_INDEX_HI')))
+        elif name.startswith('_VERT_INDEX.') and name[len('_VERT_INDEX.'):].isdigit():
+            suffix = name[len('_VERT_INDEX'):]
+            layers.append((int(suffix[1:]), layer, int_layers.get('_VERT_INDEX_HI' + suffix)))
+    return sorted(layers, key=lambda item: item[0])
+
+def get_edge_sets(obj):
+    # one edge set per index layer, the visible edges split into the untagged ones and a list per tier
+    if obj.type != 'MESH':
+        return None
+
+    bm = bmesh.new()
+    bm.from_mesh(obj.data)
+    bm.verts.ensure_lookup_table()
+
+    index_layers = get_index_layers(bm)
+    visible_edge_layer = bm.edges.layers.int.get('_VISIBLE_EDGE')
+    tier_layer = bm.edges.layers.int.get('_EDGE_TIER')
+    color_layer = bm.edges.layers.int.get('_EDGE_COLOR')
+
+    if not index_layers or visible_edge_layer is None:
+        bm.free()
+        return None
+
+    # An edge belongs to the first layer that identifies both of its vertices. Layers with a
+    # '_VERT_INDEX_HI' leave 0 unset, and an id carried by several vertices (geometry copied after
+    # add_indices) identifies none of them, since the runtime would pick whichever comes first in
+    # the exported vertex order. Older layers number from 0, there the first vertex with an id wins
+    # like it does at runtime, which only matches as long as the export keeps the vertex order
+    owners = []
+    for number, layer, high_layer in index_layers:
+        ids = {}
+        shared = set()
+        for vert in bm.verts:
+            vid = vert_id(vert, layer, high_layer)
+            if high_layer is not None and vid == 0:
+                continue
+            if vid in ids:
+                shared.add(vid)
+            else:
+                ids[vid] = vert.index
+        if high_layer is not None:
+            for vid in shared:
+                del ids[vid]
+        owners.append((number, layer, high_layer, ids))
+
+    # index layer to tier name, or None for untagged, to its edges and their colors
+    sets = {}
+    unresolved = 0
+    for edge in bm.edges:
+        if edge[visible_edge_layer] != 1:
+            continue
+        v1, v2 = edge.verts
+        owner = next(
+            (
+                (number, layer, high_layer)
+                for number, layer, high_layer, ids in owners
+                if ids.get(vert_id(v1, layer, high_layer)) == v1.index
+                and ids.get(vert_id(v2, layer, high_layer)) == v2.index
+            ),
+            None,
+        )
+        if owner is None:
+            unresolved += 1
+            continue
+        number, layer, high_layer = owner
+        tier = EDGE_TIERS.get(edge[tier_layer]) if tier_layer is not None else None
+        color = unpack_edge_color(edge[color_layer]) if color_layer is not None else None
+        edges, colors = sets.setdefault(number, {}).setdefault(tier, ([], []))
+        edges.append([vert_id(v1, layer, high_layer), vert_id(v2, layer, high_layer)])
+        colors.append(color)
+
+    bm.free()
+    if unresolved:
+        print(f"{obj.name}: {unresolved} visible edges have no index layer that identifies them, run add_indices again")
+
+    edge_sets = []
+    for number, groups in sorted(sets.items()):
+        # colors are only written when an edge has one, null entries keep the vertex colors
+        untagged, untagged_colors = groups.pop(None, ([], []))
+        edge_set = {"visibleEdges": untagged, "indexLayer": number}
+        if any(untagged_colors):
+            edge_set["edgeColors"] = untagged_colors
+        edge_groups = []
+        for tier, (edges, colors) in groups.items():
+            group = {"tier": tier, "edges": edges}
+            if any(colors):
+                group["colors"] = colors
+            edge_groups.append(group)
+        if edge_groups:
+            edge_set["edgeGroups"] = edge_groups
+        edge_sets.append(edge_set)
+    return edge_sets
+
 def get_visible_edges_indices(obj):
     if obj.type != 'MESH':
         print(f"Skipping {obj.name}: Not a mesh object")
@@ -218,12 +403,16 @@ def store_primitive_extras(obj):
         print("no visible edges found")
         return
     
-    primitive_index, line_list = visible_edges_indices
+    primitive_index, _ = visible_edges_indices
+    edge_sets = get_edge_sets(obj) or []
     
     # Create the extras structure
-    extras = {
-        "visibleEdges": line_list
-    }
+    # indexLayer 0 refers to '_VERT_INDEX'; joined objects keep theirs as '_VERT_INDEX.00N'
+    # and get an edgeSets entry per layer. Edges tagged with a tier go in edgeGroups
+    if len(edge_sets) == 1:
+        extras = edge_sets[0]
+    else:
+        extras = {"edgeSets": edge_sets}
     
     # Store as JSON string in custom property
     obj["gltf_primitive_extras"] = json.dumps(extras)
@@ -289,6 +478,59 @@ class VIEW3D_OT_clear_visible_edges(Operator):
             clear_visible_edges(obj)
         return {'FINISHED'}
 
+class VIEW3D_OT_set_edge_tier(Operator):
+    bl_idname = "view3d.set_edge_tier"
+    bl_label = "Set Edge Tier"
+    bl_description = "Tag the selected edges as primary, secondary or detail edges"
+
+    tier: EnumProperty(
+        name="Tier",
+        items=[
+            ('0', "Untagged", "Secondary, stored with the plain visible edges"),
+            ('1', "Primary", "Brightest, kept in every wireframe LOD"),
+            ('2', "Secondary", "Normal edges"),
+            ('3', "Detail", "Dimmer, dropped by the coarser wireframe LODs"),
+        ],
+    )
+
+    def execute(self, context):
+        for obj in context.selected_objects:
+            set_edge_tier(obj, int(self.tier))
+        return {'FINISHED'}
+
+class VIEW3D_OT_set_edge_color(Operator):
+    bl_idname = "view3d.set_edge_color"
+    bl_label = "Set Edge Color"
+    bl_description = "Give the selected edges their own line color instead of the vertex colors"
+    bl_options = {'REGISTER', 'UNDO'}
+
+    color: FloatVectorProperty(
+        name="Color",
+        subtype='COLOR',
+        size=4,
+        min=0.0,
+        max=1.0,
+        default=(1.0, 1.0, 1.0, 1.0),
+    )
+
+    def invoke(self, context, event):
+        return context.window_manager.invoke_props_dialog(self)
+
+    def execute(self, context):
+        for obj in context.selected_objects:
+            set_edge_color(obj, self.color)
+        return {'FINISHED'}
+
+class VIEW3D_OT_clear_edge_color(Operator):
+    bl_idname = "view3d.clear_edge_color"
+    bl_label = "Clear Edge Color"
+    bl_description = "Let the selected edges use the vertex colors again"
+
+    def execute(self, context):
+        for obj in context.selected_objects:
+            set_edge_color(obj, None)
+        return {'FINISHED'}
+
 class VIEW3D_OT_generate_edge_json(Operator):
     bl_idname = "view3d.generate_edge_json"
     bl_label = "Export Edge List"
@@ -360,6 +602,21 @@ class VIEW3D_PT_edge_visibility(Panel):
         row.operator("view3d.add_visible_edges", text="Add Visible Edges")
         row = box.row()
         row.operator("view3d.remove_visible_edges", text="Remove Visible Edges")
+
+        box = layout.box()
+        box.label(text="Edge Tiers")
+        row = box.row()
+        row.operator("view3d.set_edge_tier", text="Primary").tier = '1'
+        row.operator("view3d.set_edge_tier", text="Secondary").tier = '2'
+        row.operator("view3d.set_edge_tier", text="Detail").tier = '3'
+        row = box.row()
+        row.operator("view3d.set_edge_tier", text="Untag").tier = '0'
+
+        box = layout.box()
+        box.label(text="Edge Colors")
+        row = box.row()
+        row.operator("view3d.set_edge_color", text="Set Edge Color")
+        row.operator("view3d.clear_edge_color", text="Clear")
         
         
 #        
@@ -374,6 +631,9 @@ classes = (
     VIEW3D_OT_add_visible_edges,
     VIEW3D_OT_remove_visible_edges,
     VIEW3D_OT_clear_visible_edges,
+    VIEW3D_OT_set_edge_tier,
+    VIEW3D_OT_set_edge_color,
+    VIEW3D_OT_clear_edge_color,
     VIEW3D_OT_generate_edge_json,
     VIEW3D_OT_store_primitive_extras,
     VIEW3D_OT_print_primitive_extras,
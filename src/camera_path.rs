@@ -0,0 +1,354 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use bevy_egui::egui;
+use bevy_panorbit_camera::PanOrbitCamera;
+use serde::{Deserialize, Serialize};
+
+use crate::camera_plugin::{CameraMode, CameraSet, VaporwaveCamera};
+
+// Keyframed camera flights for trailers, played back in CameraMode::Path.
+// Paths are RON files, the egui panel can record new keyframes from the current view.
+
+pub const CAMERA_PATH: &str = "camera_paths/turntable.campath.ron";
+
+// seconds between a new keyframe and the previous one
+const KEYFRAME_SPACING: f32 = 2.0;
+
+pub struct CameraPathPlugin;
+
+impl Plugin for CameraPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<CameraPath>()
+            .init_asset_loader::<CameraPathLoader>()
+            .add_systems(Startup, load_camera_path)
+            .add_systems(Update, play_camera_path.after(CameraSet));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseInOut,
+    EaseIn,
+    EaseOut,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CameraKeyframe {
+    pub position: [f32; 3],
+    pub look_at: [f32; 3],
+    // vertical field of view in degrees
+    pub fov: f32,
+    // seconds from the start of the path
+    pub time: f32,
+    // easing of the segment starting at this keyframe
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    #[serde(default)]
+    pub looping: bool,
+}
+
+impl CameraPath {
+    pub fn start(&self) -> f32 {
+        self.keyframes.first().map_or(0.0, |k| k.time)
+    }
+
+    /// Time of the last keyframe, or of the return to the first one for a looping path whose
+    /// last keyframe doesn't repeat the first.
+    pub fn end(&self) -> f32 {
+        let end = self.keyframes.last().map_or(0.0, |k| k.time);
+        if self.has_closing_segment() {
+            end + KEYFRAME_SPACING
+        } else {
+            end
+        }
+    }
+
+    // the last keyframe repeats the first one
+    fn is_closed(&self) -> bool {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) if self.keyframes.len() > 2 => {
+                Vec3::from(last.position).distance(Vec3::from(first.position)) < 1e-3
+            }
+            _ => false,
+        }
+    }
+
+    // a looping path that isn't closed flies from its last keyframe back to the first one
+    fn has_closing_segment(&self) -> bool {
+        self.looping && self.keyframes.len() > 1 && !self.is_closed()
+    }
+
+    /// Position, look-at point and fov in degrees at `time`, Catmull-Rom interpolated
+    /// through the keyframes. Keyframes must be sorted by time.
+    pub fn sample(&self, time: f32) -> Option<(Vec3, Vec3, f32)> {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len().checked_sub(1)?;
+        if last == 0 {
+            let k = &keyframes[0];
+            return Some((Vec3::from(k.position), Vec3::from(k.look_at), k.fov));
+        }
+
+        let time = time.clamp(self.start(), self.end());
+        let segments = if self.has_closing_segment() { last + 1 } else { last };
+        let i = keyframes[..segments]
+            .iter()
+            .rposition(|k| k.time <= time)
+            .unwrap_or(0);
+        let k1 = &keyframes[i];
+        let (k2, k2_time) = match keyframes.get(i + 1) {
+            Some(k2) => (k2, k2.time),
+            None => (&keyframes[0], self.end()),
+        };
+
+        let span = k2_time - k1.time;
+        let t = if span > 0.0 { ((time - k1.time) / span).clamp(0.0, 1.0) } else { 1.0 };
+        let t = k1.easing.apply(t);
+
+        // the neighbours of the end segments are the end keyframes themselves, unless the path
+        // loops, then they wrap around to the other end so the tangent is continuous across the seam.
+        // A closing keyframe that repeats the first one is skipped, it's the same point.
+        let (k0, k3) = if self.looping {
+            let ring = if self.is_closed() { last } else { last + 1 };
+            (&keyframes[(i + ring - 1) % ring], &keyframes[(i + 2) % ring])
+        } else {
+            (&keyframes[i.saturating_sub(1)], &keyframes[(i + 2).min(last)])
+        };
+
+        let position = catmull_rom(k0.position, k1.position, k2.position, k3.position, t);
+        let look_at = catmull_rom(k0.look_at, k1.look_at, k2.look_at, k3.look_at, t);
+        let fov = k1.fov + (k2.fov - k1.fov) * t;
+        Some((position, look_at, fov))
+    }
+}
+
+fn catmull_rom(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3], p3: [f32; 3], t: f32) -> Vec3 {
+    let (p0, p1, p2, p3) = (Vec3::from(p0), Vec3::from(p1), Vec3::from(p2), Vec3::from(p3));
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+#[derive(Default)]
+struct CameraPathLoader;
+
+impl AssetLoader for CameraPathLoader {
+    type Asset = CameraPath;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<CameraPath, anyhow::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut path: CameraPath = ron::de::from_bytes(&bytes)?;
+        path.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(path)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campath.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraPathPlayer {
+    pub path: Handle<CameraPath>,
+    pub time: f32,
+    pub playing: bool,
+}
+
+fn load_camera_path(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(CameraPathPlayer {
+        path: assets.load(CAMERA_PATH),
+        time: 0.0,
+        playing: false,
+    });
+}
+
+fn play_camera_path(
+    time: Res<Time>,
+    camera_mode: Res<CameraMode>,
+    paths: Res<Assets<CameraPath>>,
+    mut player: ResMut<CameraPathPlayer>,
+    mut cameras: Query<(&mut Transform, &mut Projection), With<VaporwaveCamera>>,
+    // fov to go back to once the path stops driving the camera
+    mut saved_fov: Local<Option<f32>>,
+) {
+    let in_path = *camera_mode == CameraMode::Path;
+
+    if !in_path {
+        if let Some(fov) = saved_fov.take() {
+            for (_, mut projection) in cameras.iter_mut() {
+                if let Projection::Perspective(perspective) = &mut *projection {
+                    perspective.fov = fov;
+                }
+            }
+        }
+        return;
+    }
+
+    let Some(path) = paths.get(&player.path) else {
+        return;
+    };
+
+    if saved_fov.is_none() {
+        // entering path mode starts from the top
+        *saved_fov = Some(
+            cameras
+                .iter()
+                .find_map(|(_, projection)| match projection {
+                    Projection::Perspective(perspective) => Some(perspective.fov),
+                    _ => None,
+                })
+                .unwrap_or(PerspectiveProjection::default().fov),
+        );
+        player.time = path.start();
+        player.playing = true;
+    }
+
+    if player.playing {
+        player.time += time.delta_seconds();
+        let duration = path.end() - path.start();
+        if player.time > path.end() {
+            if path.looping && duration > 0.0 {
+                player.time = path.start() + (player.time - path.start()) % duration;
+            } else {
+                player.time = path.end();
+                player.playing = false;
+            }
+        }
+    }
+
+    let Some((position, look_at, fov)) = path.sample(player.time) else {
+        return;
+    };
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        *transform = Transform::from_translation(position).looking_at(look_at, Vec3::Y);
+        if let Projection::Perspective(perspective) = &mut *projection {
+            perspective.fov = fov.to_radians();
+        }
+    }
+}
+
+/// Playback and recording controls for the controls window.
+#[derive(SystemParam)]
+pub struct CameraPathEditor<'w, 's> {
+    player: ResMut<'w, CameraPathPlayer>,
+    paths: ResMut<'w, Assets<CameraPath>>,
+    cameras: Query<'w, 's, (&'static Transform, &'static PanOrbitCamera, &'static Projection), With<VaporwaveCamera>>,
+}
+
+impl CameraPathEditor<'_, '_> {
+    pub fn ui(&mut self, ui: &mut egui::Ui, camera_mode: &mut ResMut<CameraMode>) {
+        ui.heading("Camera Path");
+
+        let handle = self.player.path.clone();
+        let Some(path) = self.paths.get_mut(&handle) else {
+            ui.label(format!("loading {}", CAMERA_PATH));
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("Play").clicked() && path.keyframes.len() > 1 {
+                if **camera_mode == CameraMode::Path {
+                    self.player.time = path.start();
+                    self.player.playing = true;
+                } else {
+                    camera_mode.set_if_neq(CameraMode::Path);
+                }
+            }
+            if ui.button("Stop").clicked() {
+                camera_mode.set_if_neq(CameraMode::PanOrbit);
+            }
+            ui.checkbox(&mut path.looping, "Loop");
+        });
+
+        if **camera_mode == CameraMode::Path {
+            let mut time = self.player.time;
+            if ui
+                .add(egui::Slider::new(&mut time, path.start()..=path.end()).text("Time"))
+                .changed()
+            {
+                self.player.time = time;
+                self.player.playing = false;
+            }
+        }
+
+        ui.label(format!(
+            "{} keyframes, {:.1}s",
+            path.keyframes.len(),
+            path.end() - path.start()
+        ));
+
+        ui.horizontal(|ui| {
+            if ui.button("Add Keyframe").clicked() {
+                if let Ok((transform, pan_orbit, projection)) = self.cameras.get_single() {
+                    // the pan-orbit focus is only current while pan-orbit drives the camera
+                    let look_at = if **camera_mode == CameraMode::PanOrbit {
+                        pan_orbit.focus
+                    } else {
+                        transform.translation + transform.forward() * pan_orbit.radius.unwrap_or(5.0)
+                    };
+                    let fov = match projection {
+                        Projection::Perspective(perspective) => perspective.fov.to_degrees(),
+                        _ => 45.0,
+                    };
+                    let time = path.keyframes.last().map_or(0.0, |k| k.time + KEYFRAME_SPACING);
+                    path.keyframes.push(CameraKeyframe {
+                        position: transform.translation.to_array(),
+                        look_at: look_at.to_array(),
+                        fov,
+                        time,
+                        easing: Easing::default(),
+                    });
+                }
+            }
+            if ui.button("Remove Last").clicked() {
+                path.keyframes.pop();
+            }
+            if ui.button("Clear").clicked() {
+                path.keyframes.clear();
+            }
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui.button("Save").clicked() {
+            let file = std::path::Path::new("assets").join(CAMERA_PATH);
+            let saved = ron::ser::to_string_pretty(&*path, ron::ser::PrettyConfig::default())
+                .map_err(anyhow::Error::from)
+                .and_then(|ron| Ok(std::fs::write(&file, ron)?));
+            match saved {
+                Ok(()) => info!("saved camera path to {}", file.display()),
+                Err(e) => warn!("unable to save camera path to {}: {}", file.display(), e),
+            }
+        }
+    }
+}
@@ -1,150 +1,452 @@
-use bevy::{core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping}, prelude::*};
+use std::mem::discriminant;
+
+use bevy::{
+    core_pipeline::{
+        bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings},
+        tonemapping::Tonemapping,
+    },
+    input::mouse::MouseMotion,
+    prelude::*,
+    render::{mesh::skinning::SkinnedMesh, primitives::Aabb},
+};
+use bevy_egui::{egui, EguiContexts};
+use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
+use serde::{Deserialize, Serialize};
+
+// One HDR camera with bloom for the whole app, driven by whichever CameraMode is active.
+// Pan-orbit keeps its own state while another mode moves the camera, and is resynced from the
+// camera transform when it takes over again, so switching never jumps.
+pub struct VaporwaveCameraPlugin;
+
+impl Plugin for VaporwaveCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PanOrbitCameraPlugin)
+            .init_resource::<CameraMode>()
+            .init_resource::<GlowSettings>()
+            .add_event::<FrameEntity>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (
+                    camera_mode_keys,
+                    switch_camera_mode,
+                    frame_camera,
+                    turntable_camera,
+                    free_fly_camera,
+                    apply_glow_settings,
+                )
+                    .chain()
+                    .in_set(CameraSet),
+            );
+    }
+}
 
-pub struct CamPlugin;
+// Systems that pick the camera mode and move the camera, other camera drivers run after it
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CameraSet;
+
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+pub enum CameraMode {
+    // mouse orbit, pan and zoom
+    #[default]
+    PanOrbit,
+    // circles the pan-orbit focus, `height` above it
+    Turntable { speed: f32, radius: f32, height: f32 },
+    // WASD to move, Q/E down/up, hold right mouse to look, shift to go faster
+    FreeFly,
+    // plays the keyframed camera path, see camera_path.rs
+    Path,
+}
 
-impl Plugin for CamPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup);
-        app.add_systems(Update, (rotate_camera, move_camera));
+impl CameraMode {
+    pub const TURNTABLE: CameraMode = CameraMode::Turntable {
+        speed: 0.5,
+        radius: 5.0,
+        height: 1.5,
+    };
+}
+
+/// Bloom and tonemapping of the camera, the glow the whole look depends on.
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GlowSettings {
+    pub intensity: f32,
+    pub low_frequency_boost: f32,
+    pub threshold: f32,
+    pub threshold_softness: f32,
+    #[serde(with = "crate::presets::composite_mode")]
+    pub composite_mode: BloomCompositeMode,
+    #[serde(with = "crate::presets::tonemapping")]
+    pub tonemapping: Tonemapping,
+}
+
+impl Default for GlowSettings {
+    fn default() -> Self {
+        let bloom = BloomSettings::NATURAL;
+        Self {
+            intensity: bloom.intensity,
+            low_frequency_boost: bloom.low_frequency_boost,
+            threshold: bloom.prefilter_settings.threshold,
+            threshold_softness: bloom.prefilter_settings.threshold_softness,
+            composite_mode: bloom.composite_mode,
+            tonemapping: Tonemapping::TonyMcMapface,
+        }
     }
 }
 
-fn setup(mut commands: Commands) {
+impl GlowSettings {
+    pub fn bloom(&self) -> BloomSettings {
+        BloomSettings {
+            intensity: self.intensity,
+            low_frequency_boost: self.low_frequency_boost,
+            prefilter_settings: BloomPrefilterSettings {
+                threshold: self.threshold,
+                threshold_softness: self.threshold_softness,
+            },
+            composite_mode: self.composite_mode,
+            ..BloomSettings::NATURAL
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct VaporwaveCamera;
 
-    let h = 1.5;
+/// Moves the pan-orbit focus and radius so the entity and its descendants fill the view.
+/// F frames the last requested entity again.
+#[derive(Event)]
+pub struct FrameEntity(pub Entity);
 
+#[derive(Component, Default)]
+struct TurntableAngle(f32);
+
+#[derive(Component, Default)]
+struct FreeFlyLook {
+    yaw: f32,
+    pitch: f32,
+}
+
+fn spawn_camera(mut commands: Commands, glow: Res<GlowSettings>) {
     commands.spawn((
         Camera3dBundle {
             camera: Camera {
-                hdr: true, // 1. HDR is required for bloom
+                hdr: true, // HDR is required for bloom
                 ..default()
             },
-            tonemapping: Tonemapping::TonyMcMapface, // 2. Using a tonemapper that desaturates to white is recommended
-
-            transform: Transform::from_xyz(0.0, h, 5.0).looking_at(Vec3::new(0., h, 0.), Vec3::Y),
-
+            tonemapping: glow.tonemapping,
+            transform: Transform::from_translation(Vec3::new(0.0, 1.5, 5.0)),
             ..default()
         },
-
-        BloomSettings::NATURAL,
-
-        // BloomSettings{
-        //     // intensity: 0.65,
-        //     intensity: 1.0,
-        //     low_frequency_boost: 0.2,
-        //     low_frequency_boost_curvature: 0.95,
-        //     high_pass_frequency: 1.0,
-        //     prefilter_settings: BloomPrefilterSettings {
-        //         threshold: 0.0,
-        //         threshold_softness: 0.0,
-        //     },
-        //     composite_mode: BloomCompositeMode::EnergyConserving,
-        // },
-
-        OrbitCamera {
-            radius: 5.0,
-            speed: 1.0,
-        },
-        RotationEnabled(false),  // Start with rotation enabled
+        glow.bloom(),
+        PanOrbitCamera::default(),
+        TurntableAngle::default(),
+        FreeFlyLook::default(),
+        VaporwaveCamera,
     ));
 }
 
+// C cycles through the modes, R toggles the turntable
+fn camera_mode_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut camera_mode: ResMut<CameraMode>,
+    mut last_turntable: Local<Option<CameraMode>>,
+    mut contexts: EguiContexts,
+) {
+    if let CameraMode::Turntable { .. } = *camera_mode {
+        *last_turntable = Some(*camera_mode);
+    }
+    let turntable = last_turntable.unwrap_or(CameraMode::TURNTABLE);
 
-#[derive(Component)]
-struct RotationEnabled(bool);
-
-
+    // typing into a text field shouldn't switch modes
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
 
-#[derive(Component)]
-struct OrbitCamera {
-    radius: f32,
-    speed: f32,
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        *camera_mode = match *camera_mode {
+            CameraMode::PanOrbit => turntable,
+            CameraMode::Turntable { .. } => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Path,
+            CameraMode::Path => CameraMode::PanOrbit,
+        };
+    } else if keyboard_input.just_pressed(KeyCode::KeyR) {
+        *camera_mode = match *camera_mode {
+            CameraMode::Turntable { .. } => CameraMode::PanOrbit,
+            _ => turntable,
+        };
+    }
 }
 
+// Hands the camera over when the mode changes, tweaking a mode's settings doesn't count
+fn switch_camera_mode(
+    camera_mode: Res<CameraMode>,
+    mut previous: Local<Option<CameraMode>>,
+    mut cameras: Query<
+        (&Transform, &mut PanOrbitCamera, &mut TurntableAngle, &mut FreeFlyLook),
+        With<VaporwaveCamera>,
+    >,
+) {
+    if !camera_mode.is_changed() {
+        return;
+    }
+    let switched = previous.is_none_or(|previous| discriminant(&previous) != discriminant(&*camera_mode));
+    *previous = Some(*camera_mode);
+    if !switched {
+        return;
+    }
 
+    for (transform, mut pan_orbit, mut angle, mut look) in cameras.iter_mut() {
+        pan_orbit.enabled = *camera_mode == CameraMode::PanOrbit;
+
+        match *camera_mode {
+            CameraMode::PanOrbit => {
+                // orbit around the point in front of the camera closest to the old focus
+                let depth = (pan_orbit.target_focus - transform.translation).dot(*transform.forward());
+                let radius = if depth > 0.05 {
+                    depth
+                } else {
+                    pan_orbit.radius.unwrap_or(pan_orbit.target_radius).max(0.05)
+                };
+                let focus = transform.translation + transform.forward() * radius;
+                let offset = transform.translation - focus;
+                let yaw = offset.x.atan2(offset.z);
+                let pitch = (offset.y / radius).clamp(-1.0, 1.0).asin();
+
+                pan_orbit.focus = focus;
+                pan_orbit.target_focus = focus;
+                pan_orbit.yaw = Some(yaw);
+                pan_orbit.target_yaw = yaw;
+                pan_orbit.pitch = Some(pitch);
+                pan_orbit.target_pitch = pitch;
+                pan_orbit.radius = Some(radius);
+                pan_orbit.target_radius = radius;
+                pan_orbit.force_update = true;
+            }
+            CameraMode::Turntable { .. } => {
+                let offset = transform.translation - pan_orbit.target_focus;
+                angle.0 = offset.x.atan2(offset.z);
+            }
+            CameraMode::FreeFly => {
+                let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+                look.yaw = yaw;
+                look.pitch = pitch;
+            }
+            CameraMode::Path => {}
+        }
+    }
+}
 
-
-
-
-
-fn rotate_camera(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &OrbitCamera, &mut RotationEnabled), With<Camera>>,
+#[allow(clippy::too_many_arguments)]
+fn frame_camera(
+    mut requests: EventReader<FrameEntity>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut last_target: Local<Option<Entity>>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform, Option<&SkinnedMesh>)>,
+    joints: Query<&GlobalTransform>,
+    mut cameras: Query<(&mut PanOrbitCamera, &Projection), With<VaporwaveCamera>>,
 ) {
-    // Toggle rotation when 'R' is pressed
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
-        for (_, _, mut rotation_enabled) in query.iter_mut() {
-            rotation_enabled.0 = !rotation_enabled.0;
+    let requested = requests.read().last().map(|request| request.0);
+    if requested.is_some() {
+        *last_target = requested;
+    } else if !keyboard_input.just_pressed(KeyCode::KeyF) || contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let Some(target) = *last_target else {
+        return;
+    };
+
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for entity in std::iter::once(target).chain(children.iter_descendants(target)) {
+        let Ok((aabb, transform, skinned_mesh)) = bounds.get(entity) else {
+            continue;
+        };
+
+        match skinned_mesh {
+            // a skinned mesh's Aabb is its bind pose, the joints say where it is now
+            Some(skinned_mesh) => {
+                let mut joint_min = Vec3::splat(f32::MAX);
+                let mut joint_max = Vec3::splat(f32::MIN);
+                for joint in joints.iter_many(&skinned_mesh.joints) {
+                    joint_min = joint_min.min(joint.translation());
+                    joint_max = joint_max.max(joint.translation());
+                }
+                if joint_min.x > joint_max.x {
+                    continue;
+                }
+                // joints sit inside the mesh, pad for the skin around them
+                let padding = (joint_max - joint_min).max_element() * 0.1;
+                min = min.min(joint_min - padding);
+                max = max.max(joint_max + padding);
+            }
+            None => {
+                let (center, half_extents) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+                for corner in 0..8 {
+                    let sign = Vec3::new(
+                        if corner & 1 == 0 { -1.0 } else { 1.0 },
+                        if corner & 2 == 0 { -1.0 } else { 1.0 },
+                        if corner & 4 == 0 { -1.0 } else { 1.0 },
+                    );
+                    let point = transform.transform_point(center + half_extents * sign);
+                    min = min.min(point);
+                    max = max.max(point);
+                }
+            }
         }
     }
+    if min.x > max.x {
+        return;
+    }
 
-    for (mut transform, orbit, rotation_enabled) in query.iter_mut() {
-        if rotation_enabled.0 {
-            let angle = time.elapsed_seconds() * orbit.speed;
-            let x = orbit.radius * angle.cos();
-            let z = orbit.radius * angle.sin();
-
-            let y = 1.5;
-
-            transform.translation = Vec3::new(x, y, z);
-            transform.look_at(Vec3::new(0., y, 0.), Vec3::Y);
-        }
+    let center = (min + max) * 0.5;
+    let sphere_radius = ((max - min) * 0.5).length().max(0.1);
+    for (mut pan_orbit, projection) in cameras.iter_mut() {
+        let fov = match projection {
+            Projection::Perspective(perspective) => {
+                // fit the narrower of the vertical and horizontal fov
+                let horizontal = 2.0 * ((perspective.fov * 0.5).tan() * perspective.aspect_ratio).atan();
+                perspective.fov.min(horizontal)
+            }
+            _ => std::f32::consts::FRAC_PI_4,
+        };
+        // pan-orbit eases towards its targets, which animates the framing
+        pan_orbit.target_focus = center;
+        pan_orbit.target_radius = sphere_radius / (fov * 0.5).sin() * 1.05;
     }
 }
 
+fn apply_glow_settings(
+    glow: Res<GlowSettings>,
+    mut cameras: Query<(&mut BloomSettings, &mut Tonemapping), With<VaporwaveCamera>>,
+) {
+    if !glow.is_changed() {
+        return;
+    }
+    for (mut bloom, mut tonemapping) in cameras.iter_mut() {
+        *bloom = glow.bloom();
+        *tonemapping = glow.tonemapping;
+    }
+}
 
+fn turntable_camera(
+    time: Res<Time>,
+    camera_mode: Res<CameraMode>,
+    mut cameras: Query<(&mut Transform, &PanOrbitCamera, &mut TurntableAngle), With<VaporwaveCamera>>,
+) {
+    let CameraMode::Turntable { speed, radius, height } = *camera_mode else {
+        return;
+    };
+
+    for (mut transform, pan_orbit, mut angle) in cameras.iter_mut() {
+        angle.0 += speed * time.delta_seconds();
+        let focus = pan_orbit.target_focus;
+        transform.translation = focus + Vec3::new(radius * angle.0.sin(), height, radius * angle.0.cos());
+        transform.look_at(focus, Vec3::Y);
+    }
+}
 
-// fn rotate_camera(
-//     time: Res<Time>,
-//     mut query: Query<(&mut Transform, &OrbitCamera), With<Camera>>,
-//     keyboard_input: Res<ButtonInput<KeyCode>>,
-// ) {
+fn free_fly_camera(
+    time: Res<Time>,
+    camera_mode: Res<CameraMode>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut contexts: EguiContexts,
+    mut cameras: Query<(&mut Transform, &mut FreeFlyLook), With<VaporwaveCamera>>,
+) {
+    // always drain, so a drag made in another mode doesn't jump the view later
+    let delta: Vec2 = mouse_motion.read().map(|motion| motion.delta).sum();
+    if *camera_mode != CameraMode::FreeFly {
+        return;
+    }
 
+    let looking = mouse_input.pressed(MouseButton::Right) && !contexts.ctx_mut().wants_pointer_input();
+    let typing = contexts.ctx_mut().wants_keyboard_input();
+    let sensitivity = 0.003;
 
+    let mut direction = Vec3::ZERO;
+    for (key, axis) in [
+        (KeyCode::KeyW, Vec3::NEG_Z),
+        (KeyCode::KeyS, Vec3::Z),
+        (KeyCode::KeyA, Vec3::NEG_X),
+        (KeyCode::KeyD, Vec3::X),
+        (KeyCode::KeyQ, Vec3::NEG_Y),
+        (KeyCode::KeyE, Vec3::Y),
+    ] {
+        if keyboard_input.pressed(key) && !typing {
+            direction += axis;
+        }
+    }
+    let speed = if keyboard_input.pressed(KeyCode::ShiftLeft) { 8.0 } else { 2.0 };
 
-//     for (mut transform, orbit) in query.iter_mut() {
-//         let angle = time.elapsed_seconds() * orbit.speed;
-//         let x = orbit.radius * angle.cos();
-//         let z = orbit.radius * angle.sin();
+    for (mut transform, mut look) in cameras.iter_mut() {
+        if looking {
+            look.yaw -= delta.x * sensitivity;
+            look.pitch = (look.pitch - delta.y * sensitivity).clamp(-1.54, 1.54);
+            transform.rotation = Quat::from_euler(EulerRot::YXZ, look.yaw, look.pitch, 0.0);
+        }
 
-//         transform.translation = Vec3::new(x, 0.0, z);
-//         transform.look_at(Vec3::ZERO, Vec3::Y);
-//     }
-// }
+        // horizontal movement follows the view, Q/E are always world up/down
+        let forward_flat = Quat::from_rotation_y(look.yaw);
+        let step = forward_flat * Vec3::new(direction.x, 0.0, direction.z) + Vec3::Y * direction.y;
+        transform.translation += step.normalize_or_zero() * speed * time.delta_seconds();
+    }
+}
 
+/// Camera section of the controls window.
+pub fn camera_mode_ui(ui: &mut egui::Ui, camera_mode: &mut ResMut<CameraMode>) {
+    ui.heading("Camera");
 
-// system that moves the camera with keyboard controls
-fn move_camera(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<&mut Transform, With<Camera3d>>,
-) {
-    let mut direction = Vec3::ZERO;
-    let move_speed = 0.01;
+    let mut mode = **camera_mode;
+    ui.horizontal(|ui| {
+        if ui.radio(mode == CameraMode::PanOrbit, "Pan Orbit").clicked() {
+            mode = CameraMode::PanOrbit;
+        }
+        let turntable = matches!(mode, CameraMode::Turntable { .. });
+        if ui.radio(turntable, "Turntable").clicked() && !turntable {
+            mode = CameraMode::TURNTABLE;
+        }
+        if ui.radio(mode == CameraMode::FreeFly, "Free Fly").clicked() {
+            mode = CameraMode::FreeFly;
+        }
+        if ui.radio(mode == CameraMode::Path, "Path").clicked() {
+            mode = CameraMode::Path;
+        }
+    });
 
-    if keyboard_input.pressed(KeyCode::ArrowUp) {
-        direction += Vec3::Y * move_speed;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowDown) {
-        direction -= Vec3::Y * move_speed;
+    if let CameraMode::Turntable { speed, radius, height } = &mut mode {
+        ui.add(egui::Slider::new(speed, -2.0..=2.0).text("Turntable Speed"));
+        ui.add(egui::Slider::new(radius, 0.5..=20.0).text("Turntable Radius"));
+        ui.add(egui::Slider::new(height, -5.0..=10.0).text("Turntable Height"));
     }
 
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        direction -= Vec3::X * move_speed;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        direction += Vec3::X * move_speed;
-    }
-    if keyboard_input.pressed(KeyCode::KeyP) {
-        direction += Vec3::Z * move_speed;
-    }
-    if keyboard_input.pressed(KeyCode::KeyL) {
-        direction -= Vec3::Z * move_speed;
-    }
+    // only write back on change, so switch_camera_mode isn't triggered every frame
+    camera_mode.set_if_neq(mode);
+}
 
-    if direction != Vec3::ZERO {
-        for mut transform in query.iter_mut() {
-            transform.translation += direction;
-        }
-    }
-}
\ No newline at end of file
+/// Bloom and tonemapping section of the controls window.
+pub fn glow_ui(ui: &mut egui::Ui, glow: &mut ResMut<GlowSettings>) {
+    ui.heading("Glow");
+
+    let mut settings = **glow;
+    ui.add(egui::Slider::new(&mut settings.intensity, 0.0..=1.0).text("Bloom Intensity"));
+    ui.add(egui::Slider::new(&mut settings.low_frequency_boost, 0.0..=1.0).text("Low Frequency Boost"));
+    ui.add(egui::Slider::new(&mut settings.threshold, 0.0..=5.0).text("Bloom Threshold"));
+    ui.add(egui::Slider::new(&mut settings.threshold_softness, 0.0..=1.0).text("Threshold Softness"));
+
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut settings.composite_mode, BloomCompositeMode::EnergyConserving, "Energy Conserving");
+        ui.radio_value(&mut settings.composite_mode, BloomCompositeMode::Additive, "Additive");
+    });
+
+    egui::ComboBox::from_label("Tonemapping")
+        .selected_text(format!("{:?}", settings.tonemapping))
+        .show_ui(ui, |ui| {
+            for tonemapping in crate::presets::tonemapping::ALL {
+                ui.selectable_value(&mut settings.tonemapping, tonemapping, format!("{:?}", tonemapping));
+            }
+        });
+
+    glow.set_if_neq(settings);
+}
@@ -0,0 +1,342 @@
+use bevy::{
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    },
+    ecs::{query::QueryItem, system::SystemParam},
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        globals::{GlobalsBuffer, GlobalsUniform},
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+use bevy_egui::egui;
+
+use crate::camera_plugin::VaporwaveCamera;
+
+// CRT / VHS look as one fullscreen pass on the camera, after bloom and tonemapping so the effects
+// work on the final image. Effects that are off cost nothing, with all of them off the pass is skipped.
+
+pub struct CrtPlugin;
+
+impl Plugin for CrtPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<CrtSettings>::default(),
+            UniformComponentPlugin::<CrtUniform>::default(),
+        ))
+        .add_systems(Update, add_crt_settings);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<CrtNode>>(Core3d, CrtLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (Node3d::Tonemapping, CrtLabel, Node3d::EndMainPassPostProcessing),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<CrtPipeline>();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrtEffect {
+    pub enabled: bool,
+    // 0..1, 1 is as strong as still looks intentional
+    pub intensity: f32,
+}
+
+impl CrtEffect {
+    const fn new(intensity: f32) -> Self {
+        Self {
+            enabled: false,
+            intensity,
+        }
+    }
+
+    fn strength(&self) -> f32 {
+        if self.enabled {
+            self.intensity
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Post-process effects of the camera, all off by default.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct CrtSettings {
+    pub scanlines: CrtEffect,
+    pub chromatic_aberration: CrtEffect,
+    pub barrel_distortion: CrtEffect,
+    pub vignette: CrtEffect,
+    pub grain: CrtEffect,
+    pub tracking_jitter: CrtEffect,
+}
+
+impl Default for CrtSettings {
+    fn default() -> Self {
+        Self {
+            scanlines: CrtEffect::new(0.5),
+            chromatic_aberration: CrtEffect::new(0.3),
+            barrel_distortion: CrtEffect::new(0.3),
+            vignette: CrtEffect::new(0.5),
+            grain: CrtEffect::new(0.2),
+            tracking_jitter: CrtEffect::new(0.2),
+        }
+    }
+}
+
+impl CrtSettings {
+    fn effects_mut(&mut self) -> [(&'static str, &mut CrtEffect); 6] {
+        [
+            ("Scanlines", &mut self.scanlines),
+            ("Chromatic Aberration", &mut self.chromatic_aberration),
+            ("Barrel Distortion", &mut self.barrel_distortion),
+            ("Vignette", &mut self.vignette),
+            ("Grain", &mut self.grain),
+            ("Tracking Jitter", &mut self.tracking_jitter),
+        ]
+    }
+}
+
+pub use uniform::CrtUniform;
+
+// The ShaderType derive asserts each field's size from a `fn check` inside an anonymous `const _`,
+// which rustc reports as never used. Those items can't carry attributes, so the allow covers this
+// module and nothing but the derive lives in it.
+#[allow(dead_code)]
+mod uniform {
+    use bevy::{math::Vec2, prelude::Component, render::render_resource::ShaderType};
+
+    // what the shader sees, disabled effects have strength 0
+    #[derive(Component, Clone, Copy, Default, ShaderType)]
+    pub struct CrtUniform {
+        pub scanlines: f32,
+        pub chromatic_aberration: f32,
+        pub barrel_distortion: f32,
+        pub vignette: f32,
+        pub grain: f32,
+        pub tracking_jitter: f32,
+        // keeps the struct 16 byte aligned for WebGL2
+        pub _padding: Vec2,
+    }
+}
+
+impl ExtractComponent for CrtSettings {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = CrtUniform;
+
+    fn extract_component(settings: QueryItem<'_, Self::QueryData>) -> Option<CrtUniform> {
+        let uniform = CrtUniform {
+            scanlines: settings.scanlines.strength(),
+            chromatic_aberration: settings.chromatic_aberration.strength(),
+            barrel_distortion: settings.barrel_distortion.strength(),
+            vignette: settings.vignette.strength(),
+            grain: settings.grain.strength(),
+            tracking_jitter: settings.tracking_jitter.strength(),
+            _padding: Vec2::ZERO,
+        };
+        let any = [
+            uniform.scanlines,
+            uniform.chromatic_aberration,
+            uniform.barrel_distortion,
+            uniform.vignette,
+            uniform.grain,
+            uniform.tracking_jitter,
+        ]
+        .iter()
+        .any(|strength| *strength > 0.0);
+        any.then_some(uniform)
+    }
+}
+
+fn add_crt_settings(
+    mut commands: Commands,
+    cameras: Query<Entity, (Added<VaporwaveCamera>, Without<CrtSettings>)>,
+) {
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(CrtSettings::default());
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct CrtLabel;
+
+#[derive(Default)]
+struct CrtNode;
+
+impl ViewNode for CrtNode {
+    type ViewQuery = (&'static ViewTarget, &'static DynamicUniformIndex<CrtUniform>);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let crt_pipeline = world.resource::<CrtPipeline>();
+        let pipeline_id = if view_target.is_hdr() {
+            crt_pipeline.hdr_pipeline_id
+        } else {
+            crt_pipeline.pipeline_id
+        };
+        let Some(pipeline) = world.resource::<PipelineCache>().get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+
+        let Some(settings_binding) = world.resource::<ComponentUniforms<CrtUniform>>().uniforms().binding() else {
+            return Ok(());
+        };
+        let Some(globals_binding) = world.resource::<GlobalsBuffer>().buffer.binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+
+        let bind_group = render_context.render_device().create_bind_group(
+            "crt_bind_group",
+            &crt_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &crt_pipeline.sampler,
+                settings_binding,
+                globals_binding,
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("crt_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct CrtPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    // the camera renders HDR, the other one is for cameras that don't
+    hdr_pipeline_id: CachedRenderPipelineId,
+    pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for CrtPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(
+            "crt_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<CrtUniform>(true),
+                    uniform_buffer::<GlobalsUniform>(false),
+                ),
+            ),
+        );
+
+        // filtered for the sub-pixel offsets, the default clamp keeps distorted edges from wrapping
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let shader = world.load_asset("shaders/crt.wgsl");
+
+        let mut queue = |label: &'static str, format: TextureFormat| {
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some(label.into()),
+                    layout: vec![layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs: vec![],
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+        };
+        let hdr_pipeline_id = queue("crt_hdr_pipeline", ViewTarget::TEXTURE_FORMAT_HDR);
+        let pipeline_id = queue("crt_pipeline", TextureFormat::bevy_default());
+
+        Self {
+            layout,
+            sampler,
+            hdr_pipeline_id,
+            pipeline_id,
+        }
+    }
+}
+
+/// Post-process toggles and strengths for the controls window.
+#[derive(SystemParam)]
+pub struct CrtEditor<'w, 's> {
+    cameras: Query<'w, 's, &'static mut CrtSettings, With<VaporwaveCamera>>,
+}
+
+impl CrtEditor<'_, '_> {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("CRT");
+
+        for mut crt in self.cameras.iter_mut() {
+            let mut settings = *crt;
+            for (name, effect) in settings.effects_mut() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut effect.enabled, name);
+                    ui.add_enabled(effect.enabled, egui::Slider::new(&mut effect.intensity, 0.0..=1.0));
+                });
+            }
+            crt.set_if_neq(settings);
+        }
+    }
+}
@@ -7,6 +7,7 @@ use bevy::render::render_resource::{
 
 // use crate::{ATTRIBUTE_ALT_COLOR};
 use crate::{ATTRIBUTE_SMOOTHED_NORMAL};
+use crate::fog::{FADE_TO_FOG_COLOR, FADE_TO_TRANSPARENT};
 // use bitflags::bitflags;
 
 
@@ -22,9 +23,15 @@ pub struct FillMaterial {
     pub specular_strength: f32,
     #[uniform(0)]
     pub vertex_color_mode: i32,
+    // 0..1, below 1 the layer is drawn with blending
     #[uniform(0)]
-
-    pub visibility: f32,
+    pub opacity: f32,
+    // fog::FADE_TO_FOG_COLOR or fog::FADE_TO_TRANSPARENT
+    #[uniform(0)]
+    pub fog_mode: i32,
+    // hologram::Hologram strength, 0 is off
+    #[uniform(0)]
+    pub hologram: f32,
 }
 
 impl Default for FillMaterial {
@@ -35,12 +42,23 @@ impl Default for FillMaterial {
             shininess: 200.0,
             specular_strength: 1.0,
             vertex_color_mode: 0,
-            visibility: 1.0,
+            opacity: 1.0,
+            fog_mode: FADE_TO_FOG_COLOR,
+            hologram: 0.0,
         }
     }
 }
 
 impl Material for FillMaterial {
+    // see-through layers, holograms and fading to transparent need blending, everything else stays opaque
+    fn alpha_mode(&self) -> AlphaMode {
+        if self.opacity < 1.0 || self.hologram > 0.0 || self.fog_mode == FADE_TO_TRANSPARENT {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        }
+    }
+
     fn fragment_shader() -> ShaderRef {
         "shaders/fill.wgsl".into()
     }
@@ -0,0 +1,106 @@
+use bevy::{ecs::system::SystemParam, pbr::FogFalloff, prelude::*};
+use bevy_egui::egui;
+
+use crate::backdrop::color_edit;
+use crate::camera_plugin::VaporwaveCamera;
+
+// Distance fade for the fill, outline and wireframe layers. The shaders read bevy's fog uniform, so
+// a FogSettings on the camera drives it, and each material picks what far geometry fades into.
+
+// keep in sync with shaders/fog.wgsl
+pub const FADE_TO_FOG_COLOR: i32 = 0;
+pub const FADE_TO_TRANSPARENT: i32 = 1;
+
+pub struct DistanceFadePlugin;
+
+impl Plugin for DistanceFadePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_fog_shader);
+    }
+}
+
+// the layer shaders `#import bevy_vaporwave::fog`, which only resolves while the shader is loaded
+#[derive(Resource)]
+struct FogShader(#[allow(dead_code)] Handle<Shader>);
+
+fn load_fog_shader(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(FogShader(assets.load("shaders/fog.wgsl")));
+}
+
+fn default_fog() -> FogSettings {
+    FogSettings {
+        color: Color::BLACK,
+        falloff: FogFalloff::Linear {
+            start: 20.0,
+            end: 80.0,
+        },
+        ..default()
+    }
+}
+
+/// Fog controls for the controls window, `fade_mode` is `ShaderSettings::fog_mode`.
+#[derive(SystemParam)]
+pub struct FogEditor<'w, 's> {
+    commands: Commands<'w, 's>,
+    cameras: Query<'w, 's, (Entity, Option<&'static mut FogSettings>), With<VaporwaveCamera>>,
+}
+
+impl FogEditor<'_, '_> {
+    pub fn ui(&mut self, ui: &mut egui::Ui, fade_mode: &mut i32) {
+        ui.heading("Fog");
+
+        for (camera, fog) in self.cameras.iter_mut() {
+            let mut enabled = fog.is_some();
+            if ui.checkbox(&mut enabled, "Distance Fade").changed() {
+                if enabled {
+                    self.commands.entity(camera).insert(default_fog());
+                } else {
+                    self.commands.entity(camera).remove::<FogSettings>();
+                }
+            }
+            let Some(mut fog) = fog else {
+                continue;
+            };
+
+            let mut exponential = !matches!(fog.falloff, FogFalloff::Linear { .. });
+            let mut settings = fog.clone();
+            let mut changed = false;
+
+            ui.horizontal(|ui| {
+                changed |= ui.radio_value(&mut exponential, false, "Linear").changed();
+                changed |= ui.radio_value(&mut exponential, true, "Exponential").changed();
+            });
+            settings.falloff = match (exponential, settings.falloff) {
+                (false, FogFalloff::Linear { mut start, mut end }) => {
+                    changed |= ui.add(egui::Slider::new(&mut start, 0.0..=200.0).text("Start")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut end, 0.0..=400.0).text("End")).changed();
+                    FogFalloff::Linear { start, end: end.max(start) }
+                }
+                (true, FogFalloff::Exponential { mut density }) => {
+                    changed |= ui
+                        .add(egui::Slider::new(&mut density, 0.0..=0.2).text("Density"))
+                        .changed();
+                    FogFalloff::Exponential { density }
+                }
+                // switched falloff, start from something close to the default linear fog
+                (false, _) => FogFalloff::Linear {
+                    start: 20.0,
+                    end: 80.0,
+                },
+                (true, _) => FogFalloff::Exponential { density: 0.03 },
+            };
+
+            let mut color = settings.color;
+            color_edit(ui, "Color", &mut color);
+            changed |= color != settings.color;
+            settings.color = color;
+
+            if changed {
+                *fog = settings;
+            }
+        }
+
+        ui.radio_value(fade_mode, FADE_TO_FOG_COLOR, "Fade to Fog Color");
+        ui.radio_value(fade_mode, FADE_TO_TRANSPARENT, "Fade to Transparent");
+    }
+}
@@ -0,0 +1,130 @@
+// Reads glTF primitives straight into bevy meshes, without an App, asset server or GPU.
+// Used by the offline tools so they run exactly the same mesh_ops code as the runtime.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use bevy::render::{
+    mesh::{Indices, Mesh, VertexAttributeValues},
+    render_asset::RenderAssetUsages,
+    render_resource::PrimitiveTopology,
+};
+use gltf::{accessor::DataType, buffer::Data, mesh::Mode, Semantic};
+
+use crate::mesh_ops::{vert_index_hi_layer_from_name, vert_index_layer_from_name};
+use crate::{
+    ATTRIBUTE_SMOOTHED_NORMAL, ATTRIBUTE_VERT_INDEX_HI_LAYERS, ATTRIBUTE_VERT_INDEX_LAYERS,
+    ATTRIBUTE_VERT_INDEX_U32_LAYERS,
+};
+
+pub struct GltfSource {
+    pub document: gltf::Document,
+    pub buffers: Vec<Data>,
+}
+
+pub fn load_gltf(path: &Path) -> Result<GltfSource> {
+    let gltf = gltf::Gltf::open(path)?;
+    let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob)?;
+    Ok(GltfSource {
+        document: gltf.document,
+        buffers,
+    })
+}
+
+/// The extras JSON of a glTF object, as bevy exposes it in `GltfExtras::value`.
+pub fn extras_json(extras: &gltf::json::Extras) -> Option<&str> {
+    extras.as_ref().map(|raw| raw.get())
+}
+
+/// The first node instancing the mesh that carries extras.
+pub fn mesh_node_extras<'a>(document: &'a gltf::Document, mesh: &gltf::Mesh) -> Option<&'a str> {
+    document
+        .nodes()
+        .filter(|node| node.mesh().map(|m| m.index()) == Some(mesh.index()))
+        .find_map(|node| extras_json(node.extras()))
+}
+
+pub fn primitive_to_mesh(primitive: &gltf::Primitive, buffers: &[Data]) -> Result<Mesh> {
+    let topology = match primitive.mode() {
+        Mode::Triangles => PrimitiveTopology::TriangleList,
+        Mode::Lines => PrimitiveTopology::LineList,
+        mode => return Err(anyhow!("unsupported primitive mode {:?}", mode)),
+    };
+
+    let get_buffer_data = |buffer: gltf::Buffer| Some(buffers[buffer.index()].0.as_slice());
+    let reader = primitive.reader(get_buffer_data);
+
+    let mut mesh = Mesh::new(topology, RenderAssetUsages::default());
+
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .ok_or_else(|| anyhow!("primitive has no POSITION attribute"))?
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+
+    if let Some(normals) = reader.read_normals() {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.collect::<Vec<_>>());
+    }
+    if let Some(colors) = reader.read_colors(0) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.into_rgba_f32().collect::<Vec<_>>());
+    }
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs.into_f32().collect::<Vec<_>>());
+    }
+    if let Some(joints) = reader.read_joints(0) {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(joints.into_u16().collect()),
+        );
+    }
+    if let Some(weights) = reader.read_weights(0) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, weights.into_f32().collect::<Vec<_>>());
+    }
+    if let Some(indices) = reader.read_indices() {
+        mesh.insert_indices(Indices::U32(indices.into_u32().collect()));
+    }
+
+    // custom attributes, named `_NAME` in the file
+    for (semantic, accessor) in primitive.attributes() {
+        let Semantic::Extras(name) = semantic else {
+            continue;
+        };
+
+        if name == "SMOOTH_NORMAL" {
+            if let Some(values) = gltf::accessor::Iter::<[f32; 3]>::new(accessor, get_buffer_data) {
+                mesh.insert_attribute(ATTRIBUTE_SMOOTHED_NORMAL, values.collect::<Vec<_>>());
+            }
+        } else if let Some(layer) = vert_index_hi_layer_from_name(&name) {
+            if accessor.data_type() != DataType::F32 {
+                return Err(anyhow!("_{} has unsupported component type {:?}", name, accessor.data_type()));
+            }
+            if let Some(values) = gltf::accessor::Iter::<f32>::new(accessor, get_buffer_data) {
+                mesh.insert_attribute(ATTRIBUTE_VERT_INDEX_HI_LAYERS[layer].clone(), values.collect::<Vec<_>>());
+            }
+        } else if let Some(layer) = vert_index_layer_from_name(&name) {
+            match accessor.data_type() {
+                DataType::F32 => {
+                    if let Some(values) = gltf::accessor::Iter::<f32>::new(accessor, get_buffer_data) {
+                        mesh.insert_attribute(
+                            ATTRIBUTE_VERT_INDEX_LAYERS[layer].clone(),
+                            values.collect::<Vec<_>>(),
+                        );
+                    }
+                }
+                DataType::U32 => {
+                    if let Some(values) = gltf::accessor::Iter::<u32>::new(accessor, get_buffer_data) {
+                        mesh.insert_attribute(
+                            ATTRIBUTE_VERT_INDEX_U32_LAYERS[layer].clone(),
+                            values.collect::<Vec<_>>(),
+                        );
+                    }
+                }
+                data_type => {
+                    return Err(anyhow!("_{} has unsupported component type {:?}", name, data_type))
+                }
+            }
+        }
+    }
+
+    Ok(mesh)
+}
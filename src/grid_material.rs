@@ -0,0 +1,52 @@
+use bevy::{
+    prelude::*,
+    reflect::TypePath,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct GridMaterial {
+    #[uniform(0)]
+    pub color: Vec4,
+    // world units between lines
+    #[uniform(0)]
+    pub spacing: f32,
+    // in pixels
+    #[uniform(0)]
+    pub line_width: f32,
+    #[uniform(0)]
+    pub glow: f32,
+    #[uniform(0)]
+    pub brightness: f32,
+    // distance from the camera where the grid has faded out
+    #[uniform(0)]
+    pub fade_distance: f32,
+    // how far the grid has scrolled towards the camera, wraps at `spacing`
+    #[uniform(0)]
+    pub scroll: f32,
+}
+
+impl Default for GridMaterial {
+    fn default() -> Self {
+        Self {
+            color: Vec4::new(1.0, 0.1, 0.8, 1.0),
+            spacing: 1.0,
+            line_width: 1.5,
+            glow: 0.5,
+            brightness: 8.0,
+            fade_distance: 60.0,
+            scroll: 0.0,
+        }
+    }
+}
+
+impl Material for GridMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/grid.wgsl".into()
+    }
+
+    // lines add light to whatever is behind them, like the neon they imitate
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Add
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_egui::egui;
+
+use crate::fill_material::FillMaterial;
+use crate::line_material::LineMaterial;
+
+// Hologram look for the fill and wireframe layers: world space scanlines, flicker, and slices of the
+// mesh jumping sideways. Put a Hologram on a scene root, or on a single mesh to override the root.
+
+pub struct HologramPlugin;
+
+impl Plugin for HologramPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_hologram_shader)
+            .add_systems(Update, (decay_glitches, apply_holograms).chain());
+    }
+}
+
+// the layer shaders `#import bevy_vaporwave::hologram`, which only resolves while the shader is loaded
+#[derive(Resource)]
+struct HologramShader(#[allow(dead_code)] Handle<Shader>);
+
+fn load_hologram_shader(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(HologramShader(assets.load("shaders/hologram.wgsl")));
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Hologram {
+    /// Steady strength, 0 is a normal mesh and 1 a full hologram
+    pub intensity: f32,
+    /// Extra strength of the current glitch burst, dies down by `glitch_decay` per second
+    pub glitch: f32,
+    pub glitch_decay: f32,
+}
+
+impl Default for Hologram {
+    fn default() -> Self {
+        Self {
+            intensity: 1.0,
+            glitch: 0.0,
+            glitch_decay: 2.0,
+        }
+    }
+}
+
+impl Hologram {
+    /// Starts a glitch burst, e.g. when the entity takes damage. 1 is a short hiccup, 3 tears it apart.
+    pub fn glitch(&mut self, amount: f32) {
+        self.glitch = self.glitch.max(amount);
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.intensity + self.glitch
+    }
+}
+
+fn decay_glitches(time: Res<Time>, mut holograms: Query<&mut Hologram>) {
+    for mut hologram in holograms.iter_mut() {
+        if hologram.glitch > 0.0 {
+            hologram.glitch = (hologram.glitch - hologram.glitch_decay * time.delta_seconds()).max(0.0);
+        }
+    }
+}
+
+// every layer material takes the strength of the closest Hologram above it, 0 without one. Walking
+// the ancestors of every layer only happens when a Hologram or the hierarchy changed, otherwise just
+// the layers that got a new material are looked at.
+#[allow(clippy::too_many_arguments)]
+fn apply_holograms(
+    changed: Query<(), Changed<Hologram>>,
+    moved: Query<(), Changed<Parent>>,
+    mut removed: RemovedComponents<Hologram>,
+    holograms: Query<&Hologram>,
+    parents: Query<&Parent>,
+    fills: Query<(Entity, Ref<Handle<FillMaterial>>)>,
+    lines: Query<(Entity, Ref<Handle<LineMaterial>>)>,
+    mut fill_materials: ResMut<Assets<FillMaterial>>,
+    mut line_materials: ResMut<Assets<LineMaterial>>,
+) {
+    // drained every frame so an old removal doesn't trigger a pass later
+    let everything = removed.read().count() > 0 || !changed.is_empty() || !moved.is_empty();
+
+    let strength = |entity: Entity| {
+        std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find_map(|ancestor| holograms.get(ancestor).ok())
+            .map_or(0.0, Hologram::strength)
+    };
+
+    for (entity, handle) in fills.iter() {
+        if !everything && !handle.is_changed() {
+            continue;
+        }
+        let hologram = strength(entity);
+        if fill_materials.get(&*handle).is_some_and(|material| material.hologram != hologram) {
+            if let Some(material) = fill_materials.get_mut(&*handle) {
+                material.hologram = hologram;
+            }
+        }
+    }
+
+    for (entity, handle) in lines.iter() {
+        if !everything && !handle.is_changed() {
+            continue;
+        }
+        let hologram = strength(entity);
+        if line_materials.get(&*handle).is_some_and(|material| material.hologram != hologram) {
+            if let Some(material) = line_materials.get_mut(&*handle) {
+                material.hologram = hologram;
+            }
+        }
+    }
+}
+
+/// Hologram controls for the controls window, they apply to every root entity with fill or
+/// wireframe layers below it, whether it's a scene, terrain or spawned by hand.
+#[derive(SystemParam)]
+pub struct HologramEditor<'w, 's> {
+    commands: Commands<'w, 's>,
+    roots: Query<'w, 's, (Entity, Option<&'static mut Hologram>), Without<Parent>>,
+    parents: Query<'w, 's, &'static Parent>,
+    fills: Query<'w, 's, Entity, With<Handle<FillMaterial>>>,
+    lines: Query<'w, 's, Entity, With<Handle<LineMaterial>>>,
+}
+
+impl HologramEditor<'_, '_> {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Hologram");
+
+        let mut intensity = self
+            .roots
+            .iter()
+            .find_map(|(_, hologram)| hologram.map(|hologram| hologram.intensity))
+            .unwrap_or(0.0);
+        let changed = ui
+            .add(egui::Slider::new(&mut intensity, 0.0..=1.0).text("Intensity"))
+            .changed();
+        let glitch = ui.button("Glitch").clicked();
+        if !changed && !glitch {
+            return;
+        }
+
+        // only worked out on a change, the ui runs every frame
+        let mut roots: Vec<Entity> = self
+            .fills
+            .iter()
+            .chain(self.lines.iter())
+            .map(|layer| self.parents.iter_ancestors(layer).last().unwrap_or(layer))
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+
+        for root in roots {
+            let Ok((_, hologram)) = self.roots.get_mut(root) else {
+                continue;
+            };
+            match hologram {
+                Some(mut hologram) => {
+                    if changed {
+                        hologram.intensity = intensity;
+                    }
+                    if glitch {
+                        hologram.glitch(1.5);
+                    }
+                }
+                None => {
+                    let mut hologram = Hologram {
+                        intensity,
+                        ..default()
+                    };
+                    if glitch {
+                        hologram.glitch(1.5);
+                    }
+                    self.commands.entity(root).insert(hologram);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+// Headless report of what the runtime will find in a glTF: attributes, edge extras and
+// whether they resolve, vertex colors and skinning. Mirrors the checks `post_process` only
+// reports through `warn!`.
+
+use std::path::Path;
+
+use anyhow::{Error, Result};
+use bevy::render::mesh::Mesh;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::gltf_import::{extras_json, load_gltf, mesh_node_extras, primitive_to_mesh, GltfSource};
+use crate::mesh_ops::{vert_index_layer_from_name, VertexIdentity};
+use crate::parse_extras::{line_list_from_node_extras, parse_scene_edges, JsonLineList};
+
+// enough to find the offending vertices in Blender without flooding the report
+const MAX_UNRESOLVED_SAMPLES: usize = 16;
+
+/// Where a primitive's edges come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeFormat {
+    /// `gltf_primitive_extras` on the node, with `indexLayer`/`edgeSets`
+    PrimitiveExtras,
+    /// `gltf_primitive_extras` written before index layers existed
+    LegacyPrimitiveExtras,
+    /// scene level `gltf_all_selected_edges`, ignored by the runtime
+    LegacySelectedEdges,
+    /// scene level `gltf_visible_edges`, ignored by the runtime
+    LegacyVisibleEdges,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EdgeReport {
+    pub format: EdgeFormat,
+    pub edge_sets: usize,
+    pub edges: usize,
+    pub unresolved: usize,
+    pub unresolved_samples: Vec<[u32; 2]>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkinReport {
+    pub skin: Option<String>,
+    pub joints: usize,
+    pub has_joint_indices: bool,
+    pub has_joint_weights: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrimitiveReport {
+    pub mesh: usize,
+    pub mesh_name: Option<String>,
+    pub primitive: usize,
+    pub mode: String,
+    pub vertices: usize,
+    pub attributes: Vec<String>,
+    pub vert_index_layers: Vec<usize>,
+    pub baked: bool,
+    pub has_vertex_colors: bool,
+    pub edges: Vec<EdgeReport>,
+    pub skinning: Option<SkinReport>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InspectReport {
+    pub file: String,
+    pub generator: Option<String>,
+    pub primitives: Vec<PrimitiveReport>,
+    /// Why the file couldn't be inspected at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl InspectReport {
+    /// Report for a file `inspect_gltf` failed on, so it still shows up next to the others.
+    pub fn failed(file: &str, error: &Error) -> Self {
+        Self {
+            file: file.to_string(),
+            generator: None,
+            primitives: Vec::new(),
+            error: Some(format!("{:#}", error)),
+        }
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.primitives.iter().map(|p| p.errors.len()).sum::<usize>() + usize::from(self.error.is_some())
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.primitives.iter().map(|p| p.warnings.len()).sum()
+    }
+}
+
+pub fn inspect_gltf(path: &Path) -> Result<InspectReport> {
+    let source = load_gltf(path)?;
+    let mut primitives = Vec::new();
+
+    for mesh in source.document.meshes() {
+        let node_extras = mesh_node_extras(&source.document, &mesh);
+        let edge_sources = edge_sources(&source, &mesh, node_extras);
+        let skin = source
+            .document
+            .nodes()
+            .filter(|node| node.mesh().map(|m| m.index()) == Some(mesh.index()))
+            .find_map(|node| node.skin());
+
+        for primitive in mesh.primitives() {
+            primitives.push(inspect_primitive(&source, &mesh, &primitive, &edge_sources, skin.as_ref()));
+        }
+    }
+
+    Ok(InspectReport {
+        file: path.display().to_string(),
+        generator: source.document.as_json().asset.generator.clone(),
+        primitives,
+        error: None,
+    })
+}
+
+// All edge data that refers to this mesh, in the order the runtime would prefer it
+fn edge_sources(
+    source: &GltfSource,
+    mesh: &gltf::Mesh,
+    node_extras: Option<&str>,
+) -> Vec<(EdgeFormat, JsonLineList)> {
+    let mut sources = Vec::new();
+
+    if let Some(line_list) = node_extras.and_then(line_list_from_node_extras) {
+        let layered = line_list.edge_sets().any(|set| set.index_layer.is_some());
+        let format = if layered {
+            EdgeFormat::PrimitiveExtras
+        } else {
+            EdgeFormat::LegacyPrimitiveExtras
+        };
+        sources.push((format, line_list));
+    }
+
+    // scene maps are keyed by the node's `gltf_primitive_index`, older exports only had the mesh index
+    let key = node_extras
+        .and_then(|extras| serde_json::from_str::<Value>(extras).ok())
+        .and_then(|value| value.get("gltf_primitive_index")?.as_u64())
+        .unwrap_or(mesh.index() as u64)
+        .to_string();
+
+    for scene in source.document.scenes() {
+        let Some(extras) = extras_json(scene.extras()) else {
+            continue;
+        };
+        for (format, scene_key) in [
+            (EdgeFormat::LegacySelectedEdges, "gltf_all_selected_edges"),
+            (EdgeFormat::LegacyVisibleEdges, "gltf_visible_edges"),
+        ] {
+            if let Some(line_list) = parse_scene_edges(extras, scene_key)
+                .ok()
+                .and_then(|mut edges| edges.remove(&key))
+            {
+                sources.push((format, line_list));
+            }
+        }
+    }
+
+    sources
+}
+
+fn inspect_primitive(
+    source: &GltfSource,
+    mesh: &gltf::Mesh,
+    primitive: &gltf::Primitive,
+    edge_sources: &[(EdgeFormat, JsonLineList)],
+    skin: Option<&gltf::Skin>,
+) -> PrimitiveReport {
+    let attributes: Vec<String> = primitive
+        .attributes()
+        .map(|(semantic, _)| semantic.to_string())
+        .collect();
+    let has_attribute = |name: &str| attributes.iter().any(|a| a == name);
+
+    let mut report = PrimitiveReport {
+        mesh: mesh.index(),
+        mesh_name: mesh.name().map(str::to_string),
+        primitive: primitive.index(),
+        mode: format!("{:?}", primitive.mode()),
+        vertices: primitive.get(&gltf::Semantic::Positions).map_or(0, |a| a.count()),
+        vert_index_layers: Vec::new(),
+        baked: has_attribute("_SMOOTH_NORMAL"),
+        has_vertex_colors: has_attribute("COLOR_0"),
+        edges: Vec::new(),
+        skinning: None,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        attributes: Vec::new(),
+    };
+
+    report.vert_index_layers = attributes
+        .iter()
+        .filter_map(|name| vert_index_layer_from_name(name))
+        .collect();
+    report.vert_index_layers.sort_unstable();
+    report.vert_index_layers.dedup();
+
+    if has_attribute("JOINTS_0") || has_attribute("WEIGHTS_0") || skin.is_some() {
+        let skinning = SkinReport {
+            skin: skin.and_then(|s| s.name()).map(str::to_string),
+            joints: skin.map_or(0, |s| s.joints().count()),
+            has_joint_indices: has_attribute("JOINTS_0"),
+            has_joint_weights: has_attribute("WEIGHTS_0"),
+        };
+        if skin.is_none() {
+            report.warnings.push("has joint attributes but no node skins it".to_string());
+        } else if !skinning.has_joint_indices || !skinning.has_joint_weights {
+            report.errors.push("skinned node without JOINTS_0/WEIGHTS_0".to_string());
+        }
+        report.skinning = Some(skinning);
+    }
+    report.attributes = attributes;
+
+    match primitive.mode() {
+        gltf::mesh::Mode::Triangles => {}
+        // baked wireframe, its edges were resolved at bake time
+        gltf::mesh::Mode::Lines => return report,
+        mode => {
+            report.warnings.push(format!("{:?} primitives are not rendered", mode));
+            return report;
+        }
+    }
+
+    if !report.has_vertex_colors {
+        report.warnings.push("no COLOR_0, falls back to the default vertex color".to_string());
+    }
+
+    let bevy_mesh = match primitive_to_mesh(primitive, &source.buffers) {
+        Ok(bevy_mesh) => bevy_mesh,
+        Err(e) => {
+            report.errors.push(format!("can't be read: {:#}", e));
+            return report;
+        }
+    };
+
+    if edge_sources.is_empty() && !report.baked {
+        report.warnings.push("no edge extras, every triangle edge becomes a line".to_string());
+    }
+    if !edge_sources.is_empty()
+        && !edge_sources
+            .iter()
+            .any(|(format, _)| matches!(format, EdgeFormat::PrimitiveExtras | EdgeFormat::LegacyPrimitiveExtras))
+    {
+        report
+            .warnings
+            .push("only scene level edges, which the runtime ignores; re-export from Blender".to_string());
+    }
+
+    // baked triangles had their edges resolved into the LINES primitive and lost _VERT_INDEX
+    if report.baked {
+        return report;
+    }
+
+    for (format, line_list) in edge_sources {
+        report
+            .edges
+            .push(inspect_edges(&bevy_mesh, *format, line_list, &mut report.errors, &mut report.warnings));
+    }
+
+    report
+}
+
+fn inspect_edges(
+    mesh: &Mesh,
+    format: EdgeFormat,
+    line_list: &JsonLineList,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> EdgeReport {
+    let mut report = EdgeReport {
+        format,
+        edge_sets: line_list.edge_sets().count(),
+        edges: line_list.edge_sets().map(|set| set.tagged_edges().count()).sum(),
+        unresolved: 0,
+        unresolved_samples: Vec::new(),
+    };
+
+    for set in line_list.edge_sets() {
+        for mismatch in set.color_mismatches() {
+            warnings.push(format!("{:?} index layer {:?}: {}", format, set.index_layer, mismatch));
+        }
+    }
+
+    let index_layers = match mesh.vert_index_layers() {
+        Ok(index_layers) => index_layers,
+        Err(e) => {
+            if report.edges > 0 {
+                errors.push(format!("{:?} edges can't be resolved: {:#}", format, e));
+            }
+            report.unresolved = report.edges;
+            return report;
+        }
+    };
+
+    if index_layers.len() > 1 && line_list.edge_sets().all(|set| set.index_layer.is_none()) {
+        warnings.push(format!(
+            "{:?} edges have no indexLayer and only resolve against _VERT_INDEX, re-export to keep the edges of the other {} layers",
+            format,
+            index_layers.len() - 1
+        ));
+    }
+
+    for set in line_list.edge_sets() {
+        for edge in set.tagged_edges() {
+            if index_layers.resolve_edge(edge.vertices, set.index_layer).is_none() {
+                report.unresolved += 1;
+                if report.unresolved_samples.len() < MAX_UNRESOLVED_SAMPLES {
+                    report.unresolved_samples.push(edge.vertices);
+                }
+            }
+        }
+    }
+
+    if report.unresolved > 0 {
+        errors.push(format!(
+            "{} of {} {:?} edges don't resolve against _VERT_INDEX",
+            report.unresolved, report.edges, format
+        ));
+    }
+    report
+}
@@ -0,0 +1,631 @@
+use bevy::{
+    core_pipeline::core_3d::Transparent3d,
+    ecs::{
+        query::QueryItem,
+        system::{lifetimeless::*, SystemParam, SystemParamItem},
+    },
+    math::{Affine3A, IVec4, Vec3A},
+    pbr::{
+        MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
+    },
+    prelude::*,
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        mesh::{GpuBufferInfo, GpuMesh, MeshVertexBufferLayoutRef},
+        primitives::Aabb,
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+            RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::*,
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, NoFrustumCulling},
+        Render, RenderApp, RenderSet,
+    },
+    transform::TransformSystem,
+    utils::HashMap,
+};
+use bytemuck::{Pod, Zeroable};
+
+use crate::fill_material::FillMaterial;
+use crate::fog::FADE_TO_TRANSPARENT;
+use crate::line_lod::{LineLodSpheres, LineLods};
+use crate::line_material::LineMaterial;
+use crate::outline_material::OutlineMaterial;
+use crate::{ATTRIBUTE_EDGE_TIER, ATTRIBUTE_SMOOTHED_NORMAL};
+
+// Draws a processed vaporwave scene many times. Every fill, outline and wireframe child of the
+// source scene becomes one instanced draw, with the layer settings read from its material, so the
+// meshes and line meshes exist once however many copies there are.
+
+pub struct InstancingPlugin;
+
+impl Plugin for InstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ExtractComponentPlugin::<InstanceBatch>::default())
+            .add_systems(
+                PostUpdate,
+                update_instance_batches.after(TransformSystem::TransformPropagate),
+            );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent3d, DrawInstanced>()
+            .init_resource::<SpecializedMeshPipelines<InstancedPipeline>>()
+            .init_resource::<InstanceBuffers>()
+            .add_systems(
+                Render,
+                (
+                    queue_instanced.in_set(RenderSet::QueueMeshes),
+                    prepare_instance_buffers.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<InstancedPipeline>();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VaporwaveInstance {
+    /// Relative to the `VaporwaveInstances` entity
+    pub transform: Transform,
+    /// Multiplies the layer colors
+    pub tint: Color,
+    /// Multiplies the layer brightness
+    pub brightness: f32,
+}
+
+impl VaporwaveInstance {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            tint: Color::WHITE,
+            brightness: 1.0,
+        }
+    }
+}
+
+/// Draws every mesh of the processed scene `source` at each instance, one draw call per mesh and
+/// layer. Hide the source if it shouldn't show up itself, its layers are still used. Skinned meshes
+/// are drawn in their bind pose.
+///
+/// The copies are plainer than the source: Hologram strength isn't applied, and all copies of a wireframe share one LOD, picked by the distance to the nearest
+/// copy rather than to the source. Every layer, opaque or not, is drawn in the transparent pass
+/// sorted by the batch's distance, so copies don't sort against each other.
+#[derive(Component, Clone, Debug)]
+pub struct VaporwaveInstances {
+    pub source: Entity,
+    pub instances: Vec<VaporwaveInstance>,
+}
+
+// the batch entity of each layer child of the source
+#[derive(Component, Default)]
+struct InstanceBatches(HashMap<Entity, Entity>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum InstancedLayer {
+    Fill,
+    Outline,
+    Wireframe,
+}
+
+impl InstancedLayer {
+    fn shader_def(&self) -> &'static str {
+        match self {
+            InstancedLayer::Fill => "FILL",
+            InstancedLayer::Outline => "OUTLINE",
+            InstancedLayer::Wireframe => "WIREFRAME",
+        }
+    }
+}
+
+// matches Vertex in shaders/instanced.wgsl
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct InstanceData {
+    modes: IVec4,
+    world_from_local: Mat4,
+    // the tint, with the opacity in alpha
+    color: Vec4,
+    params: Vec4,
+    tier_brightness: Vec4,
+    // the material color, vertex colors replace it when they are used
+    base_color: Vec4,
+}
+
+#[derive(Component, Clone)]
+struct InstanceBatch {
+    layer: InstancedLayer,
+    blend: bool,
+    instances: Vec<InstanceData>,
+}
+
+impl ExtractComponent for InstanceBatch {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component(batch: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        // an empty instance buffer can't be created
+        (!batch.instances.is_empty()).then(|| batch.clone())
+    }
+}
+
+// what a layer child of the source looks like, before the per instance tint and brightness
+struct LayerLook {
+    layer: InstancedLayer,
+    color: Vec4,
+    params: Vec4,
+    vertex_color_mode: i32,
+    fog_mode: i32,
+    opacity: f32,
+    // wireframes only, per parse_extras::EdgeTier
+    tier_brightness: Vec4,
+    tier_visibility: i32,
+}
+
+impl LayerLook {
+    fn instance(&self, world_from_local: Affine3A, instance: &VaporwaveInstance) -> InstanceData {
+        let mut color = instance.tint.to_linear().to_vec4();
+        color.w *= self.opacity;
+
+        let mut params = self.params;
+        params.x *= instance.brightness;
+
+        InstanceData {
+            modes: IVec4::new(self.vertex_color_mode, self.fog_mode, self.tier_visibility, 0),
+            world_from_local: Mat4::from(world_from_local),
+            color,
+            params,
+            tier_brightness: self.tier_brightness,
+            base_color: self.color,
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct SourceMaterials<'w> {
+    fill: Res<'w, Assets<FillMaterial>>,
+    outline: Res<'w, Assets<OutlineMaterial>>,
+    line: Res<'w, Assets<LineMaterial>>,
+}
+
+impl SourceMaterials<'_> {
+    fn look(&self, materials: SourceLayerMaterials) -> Option<LayerLook> {
+        match materials {
+            (Some(fill), _, _) => self.fill.get(fill).map(|material| LayerLook {
+                layer: InstancedLayer::Fill,
+                color: material.color,
+                params: Vec4::new(1.0, material.displacement, material.shininess, material.specular_strength),
+                vertex_color_mode: material.vertex_color_mode,
+                fog_mode: material.fog_mode,
+                opacity: material.opacity,
+                tier_brightness: Vec4::ZERO,
+                tier_visibility: 0,
+            }),
+            (_, Some(outline), _) => self.outline.get(outline).map(|material| LayerLook {
+                layer: InstancedLayer::Outline,
+                color: material.color,
+                params: Vec4::new(material.brightness, material.outline_width, material.z_translate, 0.0),
+                vertex_color_mode: material.vertex_color_mode,
+                fog_mode: material.fog_mode,
+                opacity: material.opacity,
+                tier_brightness: Vec4::ZERO,
+                tier_visibility: 0,
+            }),
+            (_, _, Some(line)) => self.line.get(line).map(|material| LayerLook {
+                layer: InstancedLayer::Wireframe,
+                color: material.color,
+                params: Vec4::new(material.brightness, material.displacement, 0.0, 0.0),
+                vertex_color_mode: material.vertex_color_mode,
+                fog_mode: material.fog_mode,
+                opacity: material.opacity,
+                tier_brightness: material.tier_brightness,
+                // one bit per tier, it rides along in the modes
+                tier_visibility: (0..3)
+                    .filter(|&tier| material.tier_visibility[tier] > 0.5)
+                    .fold(0, |mask, tier| mask | 1 << tier),
+            }),
+            _ => None,
+        }
+    }
+}
+
+type SourceLayerMaterials<'a> = (
+    Option<&'a Handle<FillMaterial>>,
+    Option<&'a Handle<OutlineMaterial>>,
+    Option<&'a Handle<LineMaterial>>,
+);
+
+type SourceLayer = (
+    &'static Handle<Mesh>,
+    &'static GlobalTransform,
+    &'static Visibility,
+    Option<&'static Aabb>,
+    Option<&'static LineLods>,
+    AnyOf<(
+        &'static Handle<FillMaterial>,
+        &'static Handle<OutlineMaterial>,
+        &'static Handle<LineMaterial>,
+    )>,
+);
+
+type Batch = (
+    &'static mut InstanceBatch,
+    &'static mut Handle<Mesh>,
+    Option<&'static mut LineLods>,
+    Option<&'static mut LineLodSpheres>,
+);
+
+// Keeps a batch entity for every shown layer child of each source, holding the instance data
+fn update_instance_batches(
+    mut commands: Commands,
+    mut instancers: Query<(Entity, &VaporwaveInstances, &GlobalTransform, Option<&mut InstanceBatches>)>,
+    children: Query<&Children>,
+    transforms: Query<&GlobalTransform>,
+    source_layers: Query<SourceLayer, Without<InstanceBatch>>,
+    mut batches: Query<Batch>,
+    materials: SourceMaterials,
+) {
+    for (entity, instancer, global_transform, existing) in instancers.iter_mut() {
+        let Ok(source_transform) = transforms.get(instancer.source) else {
+            continue;
+        };
+        let source_from_world = source_transform.affine().inverse();
+
+        let mut stale = existing.as_ref().map(|batches| batches.0.clone()).unwrap_or_default();
+        let mut kept = HashMap::default();
+
+        for child in children.iter_descendants(instancer.source) {
+            let Ok((mesh, child_transform, visibility, aabb, lods, layer_materials)) = source_layers.get(child) else {
+                continue;
+            };
+            if *visibility == Visibility::Hidden {
+                continue;
+            }
+            let Some(look) = materials.look(layer_materials) else {
+                continue;
+            };
+
+            // where the layer sits in the source scene, put at every instance
+            let source_from_local = source_from_world * child_transform.affine();
+            let world_from_locals: Vec<Affine3A> = instancer
+                .instances
+                .iter()
+                .map(|instance| global_transform.affine() * instance.transform.compute_affine() * source_from_local)
+                .collect();
+            let instances = world_from_locals
+                .iter()
+                .zip(&instancer.instances)
+                .map(|(world_from_local, instance)| look.instance(*world_from_local, instance))
+                .collect();
+            // wireframe LODs go by the nearest copy, the source may be far away or hidden
+            let spheres = lods.map(|_| {
+                let (center, half_extents) = aabb.map_or((Vec3A::ZERO, Vec3A::ZERO), |aabb| {
+                    (aabb.center, aabb.half_extents)
+                });
+                LineLodSpheres(
+                    world_from_locals
+                        .iter()
+                        .map(|world_from_local| {
+                            (
+                                world_from_local.transform_point3a(center).into(),
+                                (world_from_local.matrix3 * half_extents).length(),
+                            )
+                        })
+                        .collect(),
+                )
+            });
+            let batch = InstanceBatch {
+                layer: look.layer,
+                blend: look.opacity < 1.0 || look.fog_mode == FADE_TO_TRANSPARENT,
+                instances,
+            };
+
+            let batch_entity = match stale.remove(&child) {
+                Some(batch_entity) => {
+                    if let Ok((mut existing_batch, mut batch_mesh, batch_lods, batch_spheres)) =
+                        batches.get_mut(batch_entity)
+                    {
+                        *existing_batch = batch;
+                        match (lods, batch_lods, batch_spheres, spheres) {
+                            // switch_line_lods picks the batch's mesh
+                            (Some(lods), Some(mut batch_lods), Some(mut batch_spheres), Some(spheres)) => {
+                                if batch_lods.meshes != lods.meshes {
+                                    *batch_lods = LineLods::new(lods.meshes.clone());
+                                    *batch_mesh = lods.meshes[0].clone();
+                                }
+                                *batch_spheres = spheres;
+                            }
+                            _ => {
+                                if *batch_mesh != *mesh {
+                                    *batch_mesh = mesh.clone();
+                                }
+                            }
+                        }
+                    }
+                    batch_entity
+                }
+                None => {
+                    let batch_mesh = lods.map_or(mesh, |lods| &lods.meshes[0]).clone();
+                    let mut batch_commands =
+                        commands.spawn((batch_mesh, SpatialBundle::default(), NoFrustumCulling, batch));
+                    if let (Some(lods), Some(spheres)) = (lods, spheres) {
+                        batch_commands.insert((LineLods::new(lods.meshes.clone()), spheres));
+                    }
+                    let batch_entity = batch_commands.id();
+                    commands.entity(entity).add_child(batch_entity);
+                    batch_entity
+                }
+            };
+            kept.insert(child, batch_entity);
+        }
+
+        // layers that were hidden or despawned since the last frame
+        for batch_entity in stale.into_values() {
+            commands.entity(batch_entity).despawn_recursive();
+        }
+        match existing {
+            Some(mut existing) => existing.0 = kept,
+            None => {
+                commands.entity(entity).insert(InstanceBatches(kept));
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn queue_instanced(
+    transparent_3d_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    instanced_pipeline: Res<InstancedPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancedPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<GpuMesh>>,
+    render_mesh_instances: Res<RenderMeshInstances>,
+    batches: Query<(Entity, &InstanceBatch)>,
+    mut transparent_render_phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    views: Query<(Entity, &ExtractedView)>,
+) {
+    let draw_instanced = transparent_3d_draw_functions.read().id::<DrawInstanced>();
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+
+    for (view_entity, view) in views.iter() {
+        let Some(transparent_phase) = transparent_render_phases.get_mut(&view_entity) else {
+            continue;
+        };
+
+        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+        let rangefinder = view.rangefinder3d();
+        for (entity, batch) in batches.iter() {
+            let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+            let mut mesh_key = view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            if batch.blend {
+                mesh_key |= MeshPipelineKey::BLEND_ALPHA;
+            }
+            let key = InstancedPipelineKey {
+                mesh_key,
+                layer: batch.layer,
+            };
+            let pipeline = match pipelines.specialize(&pipeline_cache, &instanced_pipeline, key, &mesh.layout) {
+                Ok(pipeline) => pipeline,
+                Err(e) => {
+                    error!("unable to specialize the instanced pipeline: {}", e);
+                    continue;
+                }
+            };
+            transparent_phase.add(Transparent3d {
+                entity,
+                pipeline,
+                draw_function: draw_instanced,
+                distance: rangefinder.distance_translation(&mesh_instance.translation),
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::NONE,
+            });
+        }
+    }
+}
+
+struct InstanceBuffer {
+    buffer: Buffer,
+    length: usize,
+}
+
+// kept across frames by batch entity, the render world's entities are cleared every frame
+#[derive(Resource, Default)]
+struct InstanceBuffers(HashMap<Entity, InstanceBuffer>);
+
+// Writes each batch into its buffer from the last frame, only making a new one when it grew
+fn prepare_instance_buffers(
+    batches: Query<(Entity, &InstanceBatch)>,
+    mut buffers: ResMut<InstanceBuffers>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    buffers.0.retain(|entity, _| batches.contains(*entity));
+
+    for (entity, batch) in batches.iter() {
+        let contents: &[u8] = bytemuck::cast_slice(batch.instances.as_slice());
+        match buffers.0.get_mut(&entity) {
+            Some(existing) if existing.buffer.size() >= contents.len() as u64 => {
+                render_queue.write_buffer(&existing.buffer, 0, contents);
+                existing.length = batch.instances.len();
+            }
+            _ => {
+                let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+                    label: Some("vaporwave instance buffer"),
+                    contents,
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                });
+                buffers.0.insert(
+                    entity,
+                    InstanceBuffer {
+                        buffer,
+                        length: batch.instances.len(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct InstancedPipelineKey {
+    mesh_key: MeshPipelineKey,
+    layer: InstancedLayer,
+}
+
+#[derive(Resource)]
+struct InstancedPipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for InstancedPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            shader: world.load_asset("shaders/instanced.wgsl"),
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancedPipeline {
+    type Key = InstancedPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key.mesh_key, layout)?;
+
+        // the mesh pipeline goes by the vertex layout and picks the skinned bind group for meshes
+        // with joints, but batches have no SkinnedMesh and get the plain one. The joint attributes
+        // are left out below as well, so skinned sources draw in their bind pose.
+        descriptor.layout[1] = self.mesh_pipeline.mesh_layouts.model_only.clone();
+        descriptor.vertex.shader_defs.retain(|def| *def != "SKINNED".into());
+
+        // same locations as the layer materials use
+        let mut attributes = vec![
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+        ];
+        if layout.0.contains(Mesh::ATTRIBUTE_COLOR) {
+            attributes.push(Mesh::ATTRIBUTE_COLOR.at_shader_location(5));
+        }
+        if key.layer == InstancedLayer::Outline {
+            attributes.push(ATTRIBUTE_SMOOTHED_NORMAL.at_shader_location(8));
+        }
+        // line.wgsl reads the tier at 9, here that's taken by the instance data
+        let edge_tiers = key.layer == InstancedLayer::Wireframe && layout.0.contains(ATTRIBUTE_EDGE_TIER);
+        if edge_tiers {
+            attributes.push(ATTRIBUTE_EDGE_TIER.at_shader_location(2));
+        }
+
+        let vec4 = VertexFormat::Float32x4.size();
+        let instance_attribute = |format, offset, shader_location| VertexAttribute {
+            format,
+            offset,
+            shader_location,
+        };
+        let instance_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                instance_attribute(VertexFormat::Sint32x4, 0, 9),
+                instance_attribute(VertexFormat::Float32x4, vec4, 10),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 2, 11),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 3, 12),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 4, 13),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 5, 14),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 6, 15),
+                // past the 16 locations wgpu guarantees, so it goes in a gap the mesh doesn't use
+                instance_attribute(VertexFormat::Float32x4, vec4 * 7, 3),
+                instance_attribute(VertexFormat::Float32x4, vec4 * 8, 4),
+            ],
+        };
+        descriptor.vertex.buffers = vec![layout.0.get_layout(&attributes)?, instance_layout];
+
+        let layer_def: ShaderDefVal = key.layer.shader_def().into();
+        descriptor.vertex.shader = self.shader.clone();
+        descriptor.vertex.shader_defs.push(layer_def.clone());
+        if edge_tiers {
+            descriptor.vertex.shader_defs.push("EDGE_TIERS".into());
+        }
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+            fragment.shader_defs.push(layer_def);
+        }
+        Ok(descriptor)
+    }
+}
+
+type DrawInstanced = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    DrawMeshInstanced,
+);
+
+struct DrawMeshInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
+    type Param = (
+        SRes<RenderAssets<GpuMesh>>,
+        SRes<RenderMeshInstances>,
+        SRes<InstanceBuffers>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        _item_query: Option<()>,
+        (meshes, render_mesh_instances, instance_buffers): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(instance_buffer) = instance_buffers.into_inner().0.get(&item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..instance_buffer.length as u32);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, 0..instance_buffer.length as u32);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+// The fill, outline and wireframe children of vaporwave meshes, which of them a mesh gets, and
+// spawning them only while they are turned on.
+
+#[derive(Component)]
+pub struct FillTag;
+#[derive(Component)]
+pub struct WireframeTag;
+#[derive(Component)]
+pub struct OutlineTag;
+
+/// Layers generated and shown for the meshes below this entity. Put it on a scene root, or on a
+/// single mesh to override the root. Meshes without one anywhere above them get all three, and the
+/// show toggles in the controls window still hide a layer everywhere.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct VaporwaveLayers {
+    pub fill: bool,
+    pub outline: bool,
+    pub wireframe: bool,
+}
+
+impl Default for VaporwaveLayers {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl VaporwaveLayers {
+    pub const ALL: Self = Self {
+        fill: true,
+        outline: true,
+        wireframe: true,
+    };
+
+    pub const WIREFRAME_ONLY: Self = Self {
+        fill: false,
+        outline: false,
+        wireframe: true,
+    };
+}
+
+/// Finds the `VaporwaveLayers` that apply to an entity.
+#[derive(SystemParam)]
+pub struct LayersLookup<'w, 's> {
+    layers: Query<'w, 's, &'static VaporwaveLayers>,
+    parents: Query<'w, 's, &'static Parent>,
+}
+
+impl LayersLookup<'_, '_> {
+    /// The closest `VaporwaveLayers` on `entity` or above it, all layers without one.
+    pub fn get(&self, entity: Entity) -> VaporwaveLayers {
+        std::iter::once(entity)
+            .chain(self.parents.iter_ancestors(entity))
+            .find_map(|ancestor| self.layers.get(ancestor).ok())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// How long a layer has to stay turned off before its child entity, and for the wireframe its line
+/// mesh, is despawned. Turning it back on later builds it again.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LayerLifetime {
+    pub despawn_after: f32,
+}
+
+impl Default for LayerLifetime {
+    fn default() -> Self {
+        Self { despawn_after: 5.0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayerSource {
+    // a triangle mesh, the wireframe is built from its line list
+    Triangles,
+    // a triangle mesh from vaporwave-bake, its wireframe is a separate line primitive
+    Baked,
+    // a line primitive from vaporwave-bake, only has a wireframe
+    Lines,
+}
+
+/// A processed mesh and the layer children it has right now, they are spawned when a layer is
+/// first wanted rather than all up front.
+#[derive(Component, Debug)]
+pub struct MeshLayers {
+    pub source: LayerSource,
+    pub fill: LayerSlot,
+    pub outline: LayerSlot,
+    pub wireframe: LayerSlot,
+}
+
+impl MeshLayers {
+    pub fn new(source: LayerSource) -> Self {
+        Self {
+            source,
+            fill: LayerSlot::default(),
+            outline: LayerSlot::default(),
+            wireframe: LayerSlot::default(),
+        }
+    }
+
+    /// The fade of whichever slot `child` is the layer of.
+    pub fn fade_of(&self, child: Entity) -> Option<f32> {
+        [&self.fill, &self.outline, &self.wireframe]
+            .into_iter()
+            .find(|slot| slot.entity == Some(child))
+            .map(|slot| slot.fade)
+    }
+}
+
+pub enum SlotChange {
+    Spawn,
+    Despawn(Entity),
+}
+
+#[derive(Debug, Default)]
+pub struct LayerSlot {
+    pub entity: Option<Entity>,
+    // the child's opacity right now, 0 is hidden. New layers fade in from 0
+    pub fade: f32,
+    // seconds the layer has been turned off while its child was around
+    unwanted_for: f32,
+}
+
+impl LayerSlot {
+    /// Advances the slot by a frame, saying whether its child should be spawned or despawned.
+    pub fn update(&mut self, wanted: bool, delta: f32, lifetime: &LayerLifetime) -> Option<SlotChange> {
+        if wanted {
+            self.unwanted_for = 0.0;
+            return self.entity.is_none().then_some(SlotChange::Spawn);
+        }
+
+        let entity = self.entity?;
+        self.unwanted_for += delta;
+        if self.unwanted_for < lifetime.despawn_after {
+            return None;
+        }
+        self.entity = None;
+        self.unwanted_for = 0.0;
+        self.fade = 0.0;
+        Some(SlotChange::Despawn(entity))
+    }
+
+    /// Moves the fade towards `target` by at most `step`.
+    pub fn fade_towards(&mut self, target: f32, step: f32) {
+        self.fade += (target - self.fade).clamp(-step, step);
+    }
+}
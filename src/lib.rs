@@ -0,0 +1,85 @@
+use bevy::{
+    gltf::GltfPlugin,
+    render::{mesh::MeshVertexAttribute, render_resource::VertexFormat},
+};
+
+pub mod bake;
+pub mod gltf_import;
+pub mod inspect;
+pub mod mesh_ops;
+pub mod parse_extras;
+pub mod terrain;
+
+pub const ATTRIBUTE_VERT_INDEX: MeshVertexAttribute =
+    MeshVertexAttribute::new("VERT_INDEX", 1237464976, VertexFormat::Float32);
+
+// Lossless vertex ids, converted from VERT_INDEX (and VERT_INDEX_HI) before any rebuild. glTF has
+// no unsigned int vertex attributes, so files never carry it
+pub const ATTRIBUTE_VERT_INDEX_U32: MeshVertexAttribute =
+    MeshVertexAttribute::new("VERT_INDEX_U32", 1237464977, VertexFormat::Uint32);
+
+// Joining objects in Blender keeps each object's index layer as `_VERT_INDEX.00N`
+pub const VERT_INDEX_LAYER_COUNT: usize = 8;
+
+pub const ATTRIBUTE_VERT_INDEX_LAYERS: [MeshVertexAttribute; VERT_INDEX_LAYER_COUNT] = [
+    ATTRIBUTE_VERT_INDEX,
+    MeshVertexAttribute::new("VERT_INDEX.001", 1237464981, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.002", 1237464982, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.003", 1237464983, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.004", 1237464984, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.005", 1237464985, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.006", 1237464986, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX.007", 1237464987, VertexFormat::Float32),
+];
+
+// The Blender script splits ids into 24 bit halves, `_VERT_INDEX` holds the low bits and
+// `_VERT_INDEX_HI` the rest, so both stay exact as the floats the glTF exporter writes
+pub const ATTRIBUTE_VERT_INDEX_HI_LAYERS: [MeshVertexAttribute; VERT_INDEX_LAYER_COUNT] = [
+    MeshVertexAttribute::new("VERT_INDEX_HI", 1237465010, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.001", 1237465011, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.002", 1237465012, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.003", 1237465013, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.004", 1237465014, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.005", 1237465015, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.006", 1237465016, VertexFormat::Float32),
+    MeshVertexAttribute::new("VERT_INDEX_HI.007", 1237465017, VertexFormat::Float32),
+];
+
+pub const ATTRIBUTE_VERT_INDEX_U32_LAYERS: [MeshVertexAttribute; VERT_INDEX_LAYER_COUNT] = [
+    ATTRIBUTE_VERT_INDEX_U32,
+    MeshVertexAttribute::new("VERT_INDEX_U32.001", 1237464991, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.002", 1237464992, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.003", 1237464993, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.004", 1237464994, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.005", 1237464995, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.006", 1237464996, VertexFormat::Uint32),
+    MeshVertexAttribute::new("VERT_INDEX_U32.007", 1237464997, VertexFormat::Uint32),
+];
+
+pub const ATTRIBUTE_SMOOTHED_NORMAL: MeshVertexAttribute =
+    MeshVertexAttribute::new("SmoothNormal", 723495149, VertexFormat::Float32x3);
+
+// parse_extras::EdgeTier of each line in a wireframe mesh plus EDGE_AUTHORED_COLOR, a float so the
+// bake can write it as a glTF vertex attribute
+pub const ATTRIBUTE_EDGE_TIER: MeshVertexAttribute =
+    MeshVertexAttribute::new("EDGE_TIER", 1237465001, VertexFormat::Float32);
+
+/// Added to `ATTRIBUTE_EDGE_TIER` for lines whose color came from the extras, the shaders draw them
+/// in it even with vertex colors off. Keep in sync with shaders/line.wgsl and shaders/instanced.wgsl
+pub const EDGE_AUTHORED_COLOR: u32 = 4;
+
+/// `GltfPlugin` with the custom vertex attributes written by the Blender scripts and `vaporwave-bake`.
+pub fn gltf_plugin() -> GltfPlugin {
+    let mut plugin = GltfPlugin::default()
+        .add_custom_vertex_attribute("SMOOTH_NORMAL", ATTRIBUTE_SMOOTHED_NORMAL)
+        .add_custom_vertex_attribute("EDGE_TIER", ATTRIBUTE_EDGE_TIER);
+
+    for attribute in ATTRIBUTE_VERT_INDEX_LAYERS
+        .iter()
+        .chain(ATTRIBUTE_VERT_INDEX_HI_LAYERS.iter())
+    {
+        plugin = plugin.add_custom_vertex_attribute(attribute.name, attribute.clone());
+    }
+
+    plugin
+}
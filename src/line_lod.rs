@@ -0,0 +1,143 @@
+use bevy::{prelude::*, render::primitives::Aabb, transform::TransformSystem};
+use bevy_egui::egui;
+
+use crate::camera_plugin::VaporwaveCamera;
+
+// Distance based level of detail for wireframes. Dense line meshes shimmer once their lines are
+// closer together than a pixel, so further away a wireframe swaps to a line mesh that only keeps
+// its sharper creases, built by mesh_ops::line_list_lods.
+
+pub struct LineLodPlugin;
+
+impl Plugin for LineLodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LineLodSettings>().add_systems(
+            PostUpdate,
+            switch_line_lods.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Camera distances where wireframes switch to their next coarser line mesh. Going back to a finer
+/// one waits until the wireframe is `hysteresis` (a fraction of the distance) closer than where it
+/// switched, so one sitting right at a threshold doesn't keep popping between the two.
+#[derive(Resource, Clone, Debug)]
+pub struct LineLodSettings {
+    pub enabled: bool,
+    pub distances: Vec<f32>,
+    pub hysteresis: f32,
+}
+
+impl Default for LineLodSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            distances: vec![30.0, 60.0],
+            hysteresis: 0.15,
+        }
+    }
+}
+
+impl LineLodSettings {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Wireframe LODs");
+        if !self.enabled {
+            return;
+        }
+        for (level, distance) in self.distances.iter_mut().enumerate() {
+            ui.add(egui::Slider::new(distance, 0.0..=300.0).text(format!("LOD {} Distance", level + 1)));
+        }
+        ui.add(egui::Slider::new(&mut self.hysteresis, 0.0..=0.5).text("LOD Hysteresis"));
+    }
+}
+
+/// The line meshes of a wireframe from full detail to coarsest, the entity shows `current`.
+#[derive(Component, Debug)]
+pub struct LineLods {
+    pub meshes: Vec<Handle<Mesh>>,
+    current: usize,
+}
+
+/// World space bounding spheres of the places a wireframe is drawn, for entities that aren't drawn
+/// at their own transform like instance batches. The LOD follows the nearest one.
+#[derive(Component, Clone, Debug, Default)]
+pub struct LineLodSpheres(pub Vec<(Vec3, f32)>);
+
+impl LineLods {
+    pub fn new(meshes: Vec<Handle<Mesh>>) -> Self {
+        Self { meshes, current: 0 }
+    }
+
+    // the level to show at `distance`, moving at most as far as the thresholds say from the current one
+    fn level_at(&self, distance: f32, settings: &LineLodSettings) -> usize {
+        if !settings.enabled {
+            return 0;
+        }
+        let mut level = self.current.min(self.meshes.len() - 1);
+        while level + 1 < self.meshes.len()
+            && settings.distances.get(level).is_some_and(|&threshold| distance > threshold)
+        {
+            level += 1;
+        }
+        while level > 0
+            && settings
+                .distances
+                .get(level - 1)
+                .is_none_or(|&threshold| distance < threshold * (1.0 - settings.hysteresis))
+        {
+            level -= 1;
+        }
+        level
+    }
+}
+
+type Wireframe = (
+    &'static mut LineLods,
+    &'static mut Handle<Mesh>,
+    &'static GlobalTransform,
+    Option<&'static Aabb>,
+    Option<&'static LineLodSpheres>,
+);
+
+// Swaps each wireframe's mesh for the LOD that fits its distance from the camera
+fn switch_line_lods(
+    settings: Res<LineLodSettings>,
+    cameras: Query<&GlobalTransform, With<VaporwaveCamera>>,
+    mut wireframes: Query<Wireframe>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    let camera = camera.translation();
+
+    for (mut lods, mut mesh, transform, aabb, spheres) in wireframes.iter_mut() {
+        if lods.meshes.is_empty() {
+            continue;
+        }
+        // distance to the bounding sphere, so big wireframes don't coarsen while the camera is
+        // close to one end of them
+        let distance = match spheres {
+            Some(spheres) => spheres
+                .0
+                .iter()
+                .map(|(center, radius)| (center.distance(camera) - radius).max(0.0))
+                .fold(f32::MAX, f32::min),
+            None => {
+                let (center, radius) = match aabb {
+                    Some(aabb) => (
+                        transform.transform_point(aabb.center.into()),
+                        (Vec3::from(aabb.half_extents) * transform.compute_transform().scale).length(),
+                    ),
+                    None => (transform.translation(), 0.0),
+                };
+                (center.distance(camera) - radius).max(0.0)
+            }
+        };
+
+        let level = lods.level_at(distance, &settings);
+        if level != lods.current {
+            lods.current = level;
+            *mesh = lods.meshes[level].clone();
+        }
+    }
+}
@@ -5,7 +5,8 @@ use bevy::{
 };
 
 // use crate::{ATTRIBUTE_ALT_COLOR};
-use crate::{ATTRIBUTE_SMOOTHED_NORMAL};
+use crate::{ATTRIBUTE_EDGE_TIER, ATTRIBUTE_SMOOTHED_NORMAL};
+use crate::fog::{FADE_TO_FOG_COLOR, FADE_TO_TRANSPARENT};
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 pub struct LineMaterial {
@@ -17,9 +18,21 @@ pub struct LineMaterial {
     pub vertex_color_mode: i32,
     #[uniform(0)]
     pub brightness: f32,
+    // 0..1, below 1 the layer is drawn with blending
     #[uniform(0)]
-
-    pub visibility: f32,
+    pub opacity: f32,
+    // fog::FADE_TO_FOG_COLOR or fog::FADE_TO_TRANSPARENT
+    #[uniform(0)]
+    pub fog_mode: i32,
+    // hologram::Hologram strength, 0 is off
+    #[uniform(0)]
+    pub hologram: f32,
+    // per parse_extras::EdgeTier, multiplies brightness
+    #[uniform(0)]
+    pub tier_brightness: Vec4,
+    // per parse_extras::EdgeTier, 0 hides the tier's lines
+    #[uniform(0)]
+    pub tier_visibility: Vec4,
 }
 
 
@@ -30,12 +43,24 @@ impl Default for LineMaterial {
             displacement: 0.0,
             vertex_color_mode: 0,
             brightness: 15.0,
-            visibility: 1.0,
+            opacity: 1.0,
+            fog_mode: FADE_TO_FOG_COLOR,
+            hologram: 0.0,
+            tier_brightness: Vec4::ONE,
+            tier_visibility: Vec4::ONE,
         }
     }
 }
 
 impl Material for LineMaterial {
+    // see-through layers, holograms and fading to transparent need blending, everything else stays opaque
+    fn alpha_mode(&self) -> AlphaMode {
+        if self.opacity < 1.0 || self.hologram > 0.0 || self.fog_mode == FADE_TO_TRANSPARENT {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        }
+    }
 
     fn vertex_shader() -> ShaderRef {
         "shaders/line.wgsl".into()
@@ -65,6 +90,11 @@ impl Material for LineMaterial {
             attributes.push(Mesh::ATTRIBUTE_JOINT_WEIGHT.at_shader_location(7));
         }
 
+        if layout.0.contains(ATTRIBUTE_EDGE_TIER) {
+            attributes.push(ATTRIBUTE_EDGE_TIER.at_shader_location(9));
+            descriptor.vertex.shader_defs.push("EDGE_TIERS".into());
+        }
+
         let vertex_layout = layout.0.get_layout(&attributes)?;
 
         descriptor.vertex.buffers = vec![vertex_layout];
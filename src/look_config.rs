@@ -0,0 +1,114 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::backdrop::SkySettings;
+use crate::camera_plugin::GlowSettings;
+use crate::presets::{color_srgba, LookPreset, LookPresets};
+use crate::ShaderSettings;
+
+// `assets/vaporwave.look.ron` holds the look, so it can be tuned from a text editor. Natively the
+// asset server watches the file and every save is applied to the running app, which the UI then
+// pushes into all materials.
+
+pub const LOOK_CONFIG_PATH: &str = "vaporwave.look.ron";
+
+pub struct LookConfigPlugin;
+
+impl Plugin for LookConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LookConfig>()
+            .init_asset_loader::<LookConfigLoader>()
+            .add_systems(Startup, load_look_config)
+            .add_systems(Update, apply_look_config);
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct LookConfig {
+    pub shader: ShaderSettings,
+    pub glow: GlowSettings,
+    #[serde(default)]
+    pub sky: SkySettings,
+    #[serde(with = "color_srgba")]
+    pub background: Color,
+    // extra named styles, listed with the presets
+    #[serde(default)]
+    pub styles: Vec<LookPreset>,
+}
+
+#[derive(Default)]
+struct LookConfigLoader;
+
+impl AssetLoader for LookConfigLoader {
+    type Asset = LookConfig;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<LookConfig, anyhow::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["look.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct LookConfigHandle(Handle<LookConfig>);
+
+fn load_look_config(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(LookConfigHandle(assets.load::<LookConfig>(LOOK_CONFIG_PATH)));
+}
+
+// everything a look config sets
+#[derive(SystemParam)]
+struct Look<'w> {
+    shader_settings: ResMut<'w, ShaderSettings>,
+    glow: ResMut<'w, GlowSettings>,
+    sky: ResMut<'w, SkySettings>,
+    clear_color: ResMut<'w, ClearColor>,
+    presets: ResMut<'w, LookPresets>,
+}
+
+impl Look<'_> {
+    fn apply(&mut self, config: &LookConfig) {
+        *self.shader_settings = config.shader.clone();
+        self.glow.set_if_neq(config.glow);
+        self.sky.set_if_neq(config.sky);
+        self.clear_color.0 = config.background;
+        self.presets.set_config_styles(&config.styles);
+    }
+}
+
+fn apply_look_config(
+    mut events: EventReader<AssetEvent<LookConfig>>,
+    handle: Res<LookConfigHandle>,
+    configs: Res<Assets<LookConfig>>,
+    mut look: Look,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        let Some(config) = configs.get(*id) else {
+            continue;
+        };
+
+        info!("applying {}", LOOK_CONFIG_PATH);
+        look.apply(config);
+    }
+}
@@ -1,37 +1,64 @@
 // force build again again again
 
+use bevy::ecs::system::SystemParam;
 use bevy::gltf::{GltfExtras, GltfSceneExtras};
 use bevy::prelude::Color;
 use bevy::{
     animation::animate_targets,
-    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
-    gltf::GltfPlugin,
     prelude::*,
-    render::{
-        mesh::{skinning::SkinnedMesh, MeshVertexAttribute},
-        render_resource::VertexFormat,
-    },
+    render::{mesh::skinning::SkinnedMesh, render_resource::PrimitiveTopology},
     scene::SceneInstanceReady,
 };
 
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
-use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
+use bevy_vaporwave::{
+    bake::is_baked_primitive,
+    gltf_plugin,
+    mesh_ops::{
+        colorize_vertex_colors, ensure_vertex_colors, line_list_lods, line_list_to_mesh, mesh_seed,
+        prepare_vaporwave_mesh, ColorizeStrategy, MeshToLineList, LINE_LOD_CREASE_ANGLES,
+    },
+    parse_extras::{line_list_from_node_extras, EdgeTier},
+    ATTRIBUTE_EDGE_TIER, ATTRIBUTE_SMOOTHED_NORMAL,
+};
+use backdrop::{BackdropEditor, BackdropPlugin, Mountains, SynthwaveGrid};
+use camera_path::{CameraPathEditor, CameraPathPlugin};
+use camera_plugin::{
+    camera_mode_ui, glow_ui, CameraMode, FrameEntity, GlowSettings, VaporwaveCameraPlugin,
+};
+use crt::{CrtEditor, CrtPlugin};
 use fill_material::FillMaterial;
-use line_material::LineMaterial;
-use mesh_ops::{
-    get_smoothed_normals, line_list_to_mesh, MeshToLineList,
+use fog::{DistanceFadePlugin, FogEditor, FADE_TO_FOG_COLOR};
+use hologram::{HologramEditor, HologramPlugin};
+use instancing::{InstancingPlugin, VaporwaveInstance, VaporwaveInstances};
+use layers::{
+    FillTag, LayerLifetime, LayerSource, LayersLookup, MeshLayers, OutlineTag, SlotChange,
+    VaporwaveLayers, WireframeTag,
 };
+use line_lod::{LineLodPlugin, LineLodSettings, LineLods};
+use line_material::LineMaterial;
 use outline_material::OutlineMaterial;
-use parse_extras::JsonLineList;
-use serde_json::Value;
+use look_config::LookConfigPlugin;
+use presets::{PresetEditor, PresetsPlugin};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+mod backdrop;
+mod camera_path;
 mod camera_plugin;
+mod crt;
 mod fill_material;
+mod fog;
+mod grid_material;
+mod hologram;
+mod instancing;
+mod layers;
+mod line_lod;
 mod line_material;
-mod mesh_ops;
+mod look_config;
 mod outline_material;
-mod parse_extras;
+mod presets;
+mod sky_material;
 
 const ASTRO_PATH: &str = "gltf/astro.gltf";
 const TORUS_PATH: &str = "gltf/torus.gltf";
@@ -46,12 +73,10 @@ enum VisibleModel {
     Sphere,
 }
 
-#[derive(Component)]
-struct FillTag;
-#[derive(Component)]
-struct WireframeTag;
-#[derive(Component)]
-struct OutlineTag;
+// Sent once post_process has set up every mesh of a scene, with the scene root
+#[derive(Event)]
+struct SceneProcessed(Entity);
+
 #[derive(Component)]
 struct AstroSceneTag;
 
@@ -61,6 +86,17 @@ struct CoupeSceneTag;
 struct SphereSceneTag;
 #[derive(Component)]
 struct TorusSceneTag;
+
+// How vertex colors are generated for the meshes of a scene, put it on the scene root.
+// Scenes without one fill missing colors with white
+#[derive(Component, Clone, Default)]
+struct Colorize {
+    strategy: ColorizeStrategy,
+    // mixed into each mesh's default seed, see `default_mesh_seed`
+    seed: u64,
+    // also recolor meshes that were exported with COLOR_0
+    overwrite: bool,
+}
 #[derive(Resource)]
 struct Animations {
     astro_animations: Vec<AnimationNodeIndex>,
@@ -69,7 +105,7 @@ struct Animations {
     coupe_graph: Handle<AnimationGraph>,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
 struct ShaderSettings {
     outline_width: f32,
     wireframe_displacement: f32,
@@ -78,10 +114,18 @@ struct ShaderSettings {
     fill_specular_strength: f32,
     brightness: f32,
     vertex_color_mode: i32,
+    #[serde(with = "presets::color_srgba")]
     color: Color,
     show_wireframe: bool,
     show_outline: bool,
     show_fill: bool,
+    // how the layers fade with the camera's FogSettings, see fog.rs
+    #[serde(default)]
+    fog_mode: i32,
+    #[serde(default)]
+    opacity: LayerOpacity,
+    #[serde(default)]
+    edge_tiers: EdgeTierLook,
 }
 
 impl Default for ShaderSettings {
@@ -98,40 +142,86 @@ impl Default for ShaderSettings {
             show_wireframe: true,
             show_outline: true,
             show_fill: true,
+            fog_mode: FADE_TO_FOG_COLOR,
+            opacity: LayerOpacity::default(),
+            edge_tiers: EdgeTierLook::default(),
         }
     }
 }
 
+// Opacity of the shown layers, turning a layer on or off fades it over `fade_time` seconds
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct LayerOpacity {
+    fill: f32,
+    outline: f32,
+    wireframe: f32,
+    fade_time: f32,
+}
 
-const ATTRIBUTE_VERT_INDEX: MeshVertexAttribute =
-    MeshVertexAttribute::new("VERT_INDEX", 1237464976, VertexFormat::Float32);
+impl Default for LayerOpacity {
+    fn default() -> Self {
+        Self {
+            fill: 1.0,
+            outline: 1.0,
+            wireframe: 1.0,
+            fade_time: 0.3,
+        }
+    }
+}
 
-const ATTRIBUTE_SMOOTHED_NORMAL: MeshVertexAttribute =
-    MeshVertexAttribute::new("SmoothNormal", 723495149, VertexFormat::Float32x3);
+// Wireframe brightness and visibility of each edge tier, indexed by EdgeTier::index
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct EdgeTierLook {
+    brightness: [f32; 3],
+    visible: [bool; 3],
+}
 
+impl Default for EdgeTierLook {
+    fn default() -> Self {
+        Self {
+            brightness: [1.5, 1.0, 0.6],
+            visible: [true; 3],
+        }
+    }
+}
 
 fn main() {
     App::new()
         .insert_resource(VisibleModel::Astro)
         .insert_resource(ClearColor(Color::BLACK))
         .insert_resource(ShaderSettings::default())
-        .add_plugins(
-            DefaultPlugins.set(
-                GltfPlugin::default()
-                    .add_custom_vertex_attribute("VERT_INDEX", ATTRIBUTE_VERT_INDEX)
-                    .add_custom_vertex_attribute("SMOOTH_NORMAL", ATTRIBUTE_SMOOTHED_NORMAL), // .add_custom_vertex_attribute("ALT_COLOR", ATTRIBUTE_ALT_COLOR),
-            ),
-        )
+        .init_resource::<LayerLifetime>()
+        .add_plugins(DefaultPlugins.set(gltf_plugin()))
         .add_plugins(EguiPlugin)
-        .add_plugins(PanOrbitCameraPlugin)
+        .add_plugins(VaporwaveCameraPlugin)
+        .add_plugins(CameraPathPlugin)
+        .add_plugins(PresetsPlugin)
+        .add_plugins(LookConfigPlugin)
+        .add_plugins(BackdropPlugin)
+        .add_plugins(CrtPlugin)
+        .add_plugins(DistanceFadePlugin)
+        .add_plugins(HologramPlugin)
+        .add_plugins(InstancingPlugin)
+        .add_plugins(LineLodPlugin)
         .add_plugins(MaterialPlugin::<FillMaterial>::default())
         .add_plugins(MaterialPlugin::<OutlineMaterial>::default())
         .add_plugins(MaterialPlugin::<LineMaterial>::default())
         .add_systems(Startup, setup)
         .add_systems(Startup, spawn)
         .add_systems(Update, play_animation_once_loaded.before(animate_targets))
+        .add_event::<SceneProcessed>()
         .add_systems(Update, post_process)
+        .add_systems(Update, frame_visible_model.after(post_process))
         .add_systems(Update, ui_system) // Add this line
+        .add_systems(
+            Update,
+            (sync_layers, fade_layers, update_layer_materials)
+                .chain()
+                .after(ui_system)
+                .after(post_process),
+        )
         .add_systems(Update, update_scene_visibility)
         // .add_systems(Update, handle_color_switching)
         .run();
@@ -142,20 +232,6 @@ fn setup(
     assets: Res<AssetServer>,
     mut graph_assets: ResMut<Assets<AnimationGraph>>,
 ) {
-    commands.spawn((
-        Camera3dBundle {
-            camera: Camera {
-                hdr: true,
-                ..default()
-            },
-            tonemapping: Tonemapping::TonyMcMapface,
-            transform: Transform::from_translation(Vec3::new(0.0, 1.5, 5.0)),
-            ..default()
-        },
-        PanOrbitCamera::default(),
-        BloomSettings::NATURAL,
-    ));
-
     // Build the animation graph for the astronaut
     let mut astro_graph = AnimationGraph::new();
     let astro_animations = astro_graph
@@ -248,30 +324,83 @@ fn spawn(mut commands: Commands, assets: Res<AssetServer>) {
             },
             // WireframeSettings::default(),
             SphereSceneTag,
+            // replaces the exported colors with a sunset gradient from the bottom up
+            Colorize {
+                strategy: ColorizeStrategy::HeightGradient {
+                    bottom: [0.1, 0.0, 1.0, 1.0],
+                    top: [1.0, 0.2, 0.6, 1.0],
+                },
+                overwrite: true,
+                ..default()
+            },
         ))
         .id();
+
+    // a field of tori drawn from the torus scene with instancing, turned on in the controls window
+    let field = (-5..=5)
+        .flat_map(|i| (-5..=5).map(move |j| (i, j)))
+        .filter(|(i, j): &(i32, i32)| i.abs() > 1 || j.abs() > 1)
+        .map(|(i, j)| {
+            let transform = Transform::from_xyz(i as f32 * 8.0, 1.0, j as f32 * 8.0)
+                .with_rotation(Quat::from_rotation_x((i * 3 + j) as f32 * 0.4))
+                .with_scale(Vec3::splat(0.6));
+            VaporwaveInstance {
+                tint: Color::hsl(((i * 7 + j * 3) * 15).rem_euclid(360) as f32, 1.0, 0.7),
+                ..VaporwaveInstance::new(transform)
+            }
+        })
+        .collect();
+    commands.spawn((
+        SpatialBundle {
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        VaporwaveInstances {
+            source: torus,
+            instances: field,
+        },
+    ));
+
+    // the grid floor, it follows the camera so only its height matters
+    commands.spawn((SpatialBundle::default(), SynthwaveGrid::default()));
+
+    // wireframe mountains behind the models, sunk a little so the valley floor doesn't fight the grid
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, -0.05, -60.0)),
+        Mountains::default(),
+        VaporwaveLayers::WIREFRAME_ONLY,
+    ));
 }
 
 
 
+// Seeds random mesh ops from the mesh asset path, `gltf/coupe.gltf#Mesh3/Primitive0`,
+// so colors are the same on every run and for every instance of a scene
+fn default_mesh_seed(mesh_handle: &Handle<Mesh>) -> u64 {
+    let Some(path) = mesh_handle.path() else {
+        return mesh_seed("", 0);
+    };
+    let path = path.to_string();
+    match path.rsplit_once("/Primitive") {
+        Some((mesh_path, primitive)) => mesh_seed(mesh_path, primitive.parse().unwrap_or(0)),
+        None => mesh_seed(&path, 0),
+    }
+}
+
 fn post_process(
     mut commands: Commands,
     mut events: EventReader<SceneInstanceReady>,
-    extras: Query<(&Parent, &GltfExtras)>,
     scene_extras: Query<&GltfSceneExtras>,
-    mesh: Query<(&Handle<Mesh>, &Parent)>,
+    mesh: Query<&Handle<Mesh>>,
+    primitive_extras: Query<&GltfExtras>,
     children: Query<&Children>,
-    mut line_materials: ResMut<Assets<LineMaterial>>,
-    mut fill_materials: ResMut<Assets<FillMaterial>>,
-    mut outline_materials: ResMut<Assets<OutlineMaterial>>, // Add FillMaterial resource
     mut mesh_assets: ResMut<Assets<Mesh>>,
-    shader_settings: Res<ShaderSettings>,
-    // mut wf: Query<&mut WireframeSettings>,
-    skinned_meshes: Query<&SkinnedMesh>,
-
+    colorize: Query<&Colorize>,
+    mut processed: EventWriter<SceneProcessed>,
 ) {
 
     for event in events.read() {
+        let colorize = colorize.get(event.parent).cloned().unwrap_or_default();
         
         // Out of laziness I iterate through the whole scene until I find the scene level extra which contains a json dictionary
         // that encodes the line lists generated in blender, with the index of the mesh as the key
@@ -279,155 +408,246 @@ fn post_process(
         // TODO: better way to locate the scene level extra
 
 
-        // Iterate through each mesh and apply the wireframe post processing
-        // If scene extras were found, it will generate the line lists according to the json dictionary
-        // Otherwise it will generate a line list for every edge of the mesh
+        // Iterate through each mesh and get it ready for the vaporwave layers,
+        // sync_layers spawns the fill, outline and wireframe children once they are turned on
 
         for this_entity in children.iter_descendants(event.parent) {
-            if let Ok((mesh_handle, parent)) = mesh.get(this_entity) {
+            if let Ok(mesh_handle) = mesh.get(this_entity) {
                 if let Some(mesh) = mesh_assets.get_mut(mesh_handle) {
                     commands
                         .entity(this_entity)
                         .remove::<Handle<StandardMaterial>>();
 
-                    let smoothed_normals: Vec<[f32; 3]> = get_smoothed_normals(mesh).unwrap();
-                    // invert_normals(&mut smoothed_normals);
-                    mesh.insert_attribute(ATTRIBUTE_SMOOTHED_NORMAL, smoothed_normals);
-                    mesh.duplicate_vertices();
-                    mesh.compute_flat_normals();
-
-                    // Check for Vertex_Color attribute
-                    if !mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_some() {
-                        warn!("Vertex_Color attribute not found");
-                        // If Vertex_Color is not present, add it with a constant color
-                        let vertex_count = mesh.count_vertices();
-                        let constant_color = [1.0, 0.0, 1.0, 0.0]; // White color, adjust as needed
-                        let colors: Vec<[f32; 4]> = vec![constant_color; vertex_count];
-                        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+                    // Line primitives written by vaporwave-bake only need the wireframe material
+                    if mesh.primitive_topology() == PrimitiveTopology::LineList {
+                        commands
+                            .entity(this_entity)
+                            .insert(MeshLayers::new(LayerSource::Lines));
+                        continue;
                     }
 
-
-                    // FILL
-
-                    let fill_material_handle = fill_materials.add(FillMaterial {
-                        color: Vec4::new(1.0, 0.0, 0.0, 1.0),
-                        displacement: 0.0,
-                        shininess: 200.0,
-                        specular_strength: 1.0,
-                        vertex_color_mode: 1,
-                        visibility: 1.0,
-                    });
-
-                    let skinned_mesh = skinned_meshes.get(this_entity).cloned(); // required for scenes with skinned mesh animations
-
-                    commands.entity(this_entity).with_children(|parent| {
-                        let mut child_entity = parent.spawn((
-                            MaterialMeshBundle {
-                                mesh: mesh_handle.clone(),
-                                material: fill_material_handle,
-                                visibility: Visibility::Inherited,
-                                ..Default::default()
-                            },
-                            FillTag,
-                        ));
-
-                        // If the original entity had a SkinnedMesh component, add it to the new entity
-                        if let Ok(skinned_mesh) = skinned_mesh {
-                            child_entity.insert(skinned_mesh);
-                        }
-                    });
-                    
-                    
-                    // OUTLINE
-
-                    // Add OutlineMaterial component
-                    let outline_material_handle = outline_materials.add(OutlineMaterial {
-                        outline_width: shader_settings.outline_width,
-                        ..default()
-                    });
-
-
-                    let skinned_mesh = skinned_meshes.get(this_entity).cloned(); // required for scenes with skinned mesh animations
-
-                    commands.entity(this_entity).with_children(|parent| {
-                        let mut child_entity = parent.spawn((
-                            MaterialMeshBundle {
-                                mesh: mesh_handle.clone(),
-                                material: outline_material_handle,
-                                visibility: Visibility::Inherited,
-                                ..Default::default()
-                            },
-                            OutlineTag,
-                        ));
-
-                        // If the original entity had a SkinnedMesh component, add it to the new entity
-                        if let Ok(skinned_mesh) = skinned_mesh {
-                            child_entity.insert(skinned_mesh);
-                        }
-                    });
-
-                    // WIRE FRAME
-
-                    // LineList stores the data required to build a mesh of lines
-                    // It can be derived from gltf extra data, or generated for every triangle in the absence
-
-                    let parsed_line_list = if let Ok(mesh_extra) = extras.get(parent.get()) {
-                        if let Ok(json_value) = serde_json::from_str::<Value>(&mesh_extra.1.value) {
-                            if let Some(v) = json_value.get("gltf_primitive_extras") {
-                                if let Ok(edge_data) =
-                                    serde_json::from_str::<JsonLineList>(v.as_str().unwrap_or(""))
-                                {
-                                    Some(edge_data)
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
+                    // Baked meshes already carry smoothed normals, and their wireframe is a separate line primitive.
+                    // They are told apart by the bake's marker, smoothed normals alone also show up when a scene
+                    // is spawned again, as the first post_process wrote them into the shared mesh asset
+                    let baked = primitive_extras
+                        .get(this_entity)
+                        .is_ok_and(|extras| is_baked_primitive(&extras.value));
+                    let prepared = baked || mesh.contains_attribute(ATTRIBUTE_SMOOTHED_NORMAL);
+
+                    if !prepared {
+                        if let Err(e) = prepare_vaporwave_mesh(mesh) {
+                            warn!("unable to prepare mesh {:?}: {}", mesh_handle.path(), e);
+                            continue;
                         }
+                    }
+
+                    let seed = default_mesh_seed(mesh_handle) ^ colorize.seed;
+                    let colorized = if colorize.overwrite {
+                        colorize_vertex_colors(mesh, &colorize.strategy, seed)
                     } else {
-                        None
+                        ensure_vertex_colors(mesh, &colorize.strategy, seed).map(|_| ())
                     };
-
-                    let line_list;
-
-                    match parsed_line_list {
-                        Some(p) => {
-                            line_list = mesh.mesh_to_line_list_from_json(&p);
-                        }
-                        None => {
-                            line_list = mesh.mesh_to_line_list();
-                        }
+                    if let Err(e) = colorized {
+                        warn!("unable to colorize mesh {:?}: {}", mesh_handle.path(), e);
                     }
 
-                    let line_mesh = line_list_to_mesh(&line_list, &mesh);
-                    let new_mesh_handle = mesh_assets.add(line_mesh);
-                    let skinned_mesh = skinned_meshes.get(this_entity).cloned(); // required for scenes with skinned mesh animations
-
-                    commands.entity(this_entity).with_children(|parent| {
-                        let mut child_entity = parent.spawn((
-                            MaterialMeshBundle {
-                                mesh: new_mesh_handle,
-                                material: line_materials.add(LineMaterial {
-                                    displacement: 1.5,
-                                    ..default()
-                                }),
-                                visibility: Visibility::Inherited,
-                                ..Default::default()
-                            },
-                            WireframeTag,
-                        ));
-
-                        // If the original entity had a SkinnedMesh component, add it to the new entity
-                        if let Ok(skinned_mesh) = skinned_mesh {
-                            child_entity.insert(skinned_mesh);
-                        }
-                    });
+                    let source = if baked {
+                        LayerSource::Baked
+                    } else {
+                        LayerSource::Triangles
+                    };
+                    commands.entity(this_entity).insert(MeshLayers::new(source));
                 }
             }
         }
+
+        processed.send(SceneProcessed(event.parent));
+    }
+}
+
+// Builds the layer children of processed meshes, update_layer_materials fills in the material settings
+#[derive(SystemParam)]
+struct LayerBuilder<'w, 's> {
+    commands: Commands<'w, 's>,
+    extras: Query<'w, 's, &'static GltfExtras>,
+    parents: Query<'w, 's, &'static Parent>,
+    skinned_meshes: Query<'w, 's, &'static SkinnedMesh>,
+    mesh_assets: ResMut<'w, Assets<Mesh>>,
+    line_materials: ResMut<'w, Assets<LineMaterial>>,
+    fill_materials: ResMut<'w, Assets<FillMaterial>>,
+    outline_materials: ResMut<'w, Assets<OutlineMaterial>>,
+}
+
+impl LayerBuilder<'_, '_> {
+    fn spawn_layer(&mut self, entity: Entity, layer: impl Bundle) -> Entity {
+        let mut child = self.commands.spawn(layer);
+        // required for scenes with skinned mesh animations
+        if let Ok(skinned_mesh) = self.skinned_meshes.get(entity) {
+            child.insert(skinned_mesh.clone());
+        }
+        let child = child.id();
+        self.commands.entity(entity).add_child(child);
+        child
+    }
+
+    fn fill(&mut self, entity: Entity, mesh: &Handle<Mesh>) -> Entity {
+        let material = self.fill_materials.add(FillMaterial {
+            vertex_color_mode: 1,
+            ..default()
+        });
+        let bundle = MaterialMeshBundle {
+            mesh: mesh.clone(),
+            material,
+            ..default()
+        };
+        self.spawn_layer(entity, (bundle, FillTag))
+    }
+
+    fn outline(&mut self, entity: Entity, mesh: &Handle<Mesh>) -> Entity {
+        let material = self.outline_materials.add(OutlineMaterial::default());
+        let bundle = MaterialMeshBundle {
+            mesh: mesh.clone(),
+            material,
+            ..default()
+        };
+        self.spawn_layer(entity, (bundle, OutlineTag))
+    }
+
+    // None while the mesh asset isn't there
+    fn wireframe(&mut self, entity: Entity, mesh_handle: &Handle<Mesh>, source: LayerSource) -> Option<Entity> {
+        let (mesh, lods) = match source {
+            LayerSource::Triangles => {
+                let mesh = self.mesh_assets.get(mesh_handle)?;
+
+                // LineList stores the data required to build a mesh of lines
+                // It can be derived from gltf extra data, or generated for every triangle in the absence
+
+                let parsed_line_list = self
+                    .parents
+                    .get(entity)
+                    .ok()
+                    .and_then(|parent| self.extras.get(parent.get()).ok())
+                    .and_then(|mesh_extra| line_list_from_node_extras(&mesh_extra.value));
+
+                let line_list = match parsed_line_list {
+                    Some(p) => mesh.mesh_to_line_list_from_json(&p),
+                    None => mesh.mesh_to_line_list(),
+                };
+
+                // the full line mesh first, then the coarser LODs
+                let line_meshes: Vec<Mesh> = line_list_lods(&line_list, mesh, &LINE_LOD_CREASE_ANGLES)
+                    .iter()
+                    .map(|lod| line_list_to_mesh(lod, mesh))
+                    .collect();
+                let handles: Vec<Handle<Mesh>> = line_meshes
+                    .into_iter()
+                    .map(|line_mesh| self.mesh_assets.add(line_mesh))
+                    .collect();
+                (handles[0].clone(), (handles.len() > 1).then(|| LineLods::new(handles)))
+            }
+            _ => (mesh_handle.clone(), None),
+        };
+        let material = self.line_materials.add(LineMaterial {
+            displacement: 1.5,
+            ..default()
+        });
+        let bundle = MaterialMeshBundle {
+            mesh,
+            material,
+            ..default()
+        };
+        let child = self.spawn_layer(entity, (bundle, WireframeTag));
+        if let Some(lods) = lods {
+            self.commands.entity(child).insert(lods);
+        }
+        Some(child)
+    }
+
+    // dropping the child also frees a generated line mesh
+    fn despawn(&mut self, child: Entity) {
+        self.commands.entity(child).despawn_recursive();
+    }
+}
+
+// The layers a mesh shows: turned on in the controls window and by its VaporwaveLayers. Line
+// primitives have no surface, baked meshes get their wireframe from a line primitive
+fn shown_layers(shader_settings: &ShaderSettings, enabled: VaporwaveLayers, source: LayerSource) -> VaporwaveLayers {
+    VaporwaveLayers {
+        fill: shader_settings.show_fill && enabled.fill && source != LayerSource::Lines,
+        outline: shader_settings.show_outline && enabled.outline && source != LayerSource::Lines,
+        wireframe: shader_settings.show_wireframe && enabled.wireframe && source != LayerSource::Baked,
+    }
+}
+
+// Spawns the layer children that are turned on but missing, and despawns the ones that have been
+// off for LayerLifetime::despawn_after
+fn sync_layers(
+    time: Res<Time>,
+    shader_settings: Res<ShaderSettings>,
+    lifetime: Res<LayerLifetime>,
+    layers: LayersLookup,
+    mut meshes: Query<(Entity, &Handle<Mesh>, &mut MeshLayers)>,
+    mut builder: LayerBuilder,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mesh, mut mesh_layers) in meshes.iter_mut() {
+        let source = mesh_layers.source;
+        let shown = shown_layers(&shader_settings, layers.get(entity), source);
+        let mesh_layers = &mut *mesh_layers;
+
+        match mesh_layers.fill.update(shown.fill, delta, &lifetime) {
+            Some(SlotChange::Spawn) => mesh_layers.fill.entity = Some(builder.fill(entity, mesh)),
+            Some(SlotChange::Despawn(child)) => builder.despawn(child),
+            None => {}
+        }
+
+        match mesh_layers.outline.update(shown.outline, delta, &lifetime) {
+            Some(SlotChange::Spawn) => mesh_layers.outline.entity = Some(builder.outline(entity, mesh)),
+            Some(SlotChange::Despawn(child)) => builder.despawn(child),
+            None => {}
+        }
+
+        match mesh_layers.wireframe.update(shown.wireframe, delta, &lifetime) {
+            Some(SlotChange::Spawn) => mesh_layers.wireframe.entity = builder.wireframe(entity, mesh, source),
+            Some(SlotChange::Despawn(child)) => builder.despawn(child),
+            None => {}
+        }
+    }
+}
+
+type SceneTags = (
+    Entity,
+    Has<AstroSceneTag>,
+    Has<CoupeSceneTag>,
+    Has<TorusSceneTag>,
+    Has<SphereSceneTag>,
+);
+
+// Frames the visible model when it changes, or once its scene has been processed
+fn frame_visible_model(
+    visible_model: Res<VisibleModel>,
+    mut processed: EventReader<SceneProcessed>,
+    scenes: Query<SceneTags>,
+    mut frame: EventWriter<FrameEntity>,
+) {
+    let visible_root = scenes.iter().find_map(|(entity, astro, coupe, torus, sphere)| {
+        let visible = match *visible_model {
+            VisibleModel::Astro => astro,
+            VisibleModel::Coupe => coupe,
+            VisibleModel::Torus => torus,
+            VisibleModel::Sphere => sphere,
+        };
+        visible.then_some(entity)
+    });
+    let Some(visible_root) = visible_root else {
+        return;
+    };
+
+    // read every event, not just up to the first match
+    let visible_processed = processed.read().filter(|scene| scene.0 == visible_root).count() > 0;
+    if visible_processed || visible_model.is_changed() {
+        frame.send(FrameEntity(visible_root));
     }
 }
 
@@ -488,20 +708,21 @@ fn play_animation_once_loaded(
 fn ui_system(
     mut contexts: EguiContexts,
     mut shader_settings: ResMut<ShaderSettings>,
-    mut outline_materials_assets: ResMut<Assets<OutlineMaterial>>,
-    outline_materials: Query<&Handle<OutlineMaterial>>,
-    mut line_materials_assets: ResMut<Assets<LineMaterial>>,
-    line_materials: Query<&Handle<LineMaterial>>,
-    mut fill_materials_assets: ResMut<Assets<FillMaterial>>,
-    fill_materials: Query<&Handle<FillMaterial>>,
     mut visible_model: ResMut<VisibleModel>,
-    mut visibility_set: ParamSet<(
-        Query<&mut Visibility, With<FillTag>>,
-        Query<&mut Visibility, With<OutlineTag>>,
-        Query<&mut Visibility, With<WireframeTag>>,
-    )>,
+    mut camera_mode: ResMut<CameraMode>,
+    mut camera_path_editor: CameraPathEditor,
+    mut glow: ResMut<GlowSettings>,
+    mut preset_editor: PresetEditor,
+    mut backdrop_editor: BackdropEditor,
+    mut crt_editor: CrtEditor,
+    mut fog_editor: FogEditor,
+    mut hologram_editor: HologramEditor,
+    mut instance_fields: Query<&mut Visibility, With<VaporwaveInstances>>,
+    mut line_lods: ResMut<LineLodSettings>,
 ) {
     egui::Window::new("Shader Controls").show(contexts.ctx_mut(), |ui| {
+        preset_editor.ui(ui, &mut shader_settings, &mut glow);
+        ui.separator();
         ui.add(
             egui::Slider::new(&mut shader_settings.outline_width, 0.0..=1.0).text("Outline Width"),
         );
@@ -527,6 +748,30 @@ fn ui_system(
         ui.radio_value(&mut *visible_model, VisibleModel::Astro, "Astro");
         ui.radio_value(&mut *visible_model, VisibleModel::Torus, "Torus");
         ui.radio_value(&mut *visible_model, VisibleModel::Sphere, "Sphere");
+        for mut visibility in instance_fields.iter_mut() {
+            let mut shown = *visibility != Visibility::Hidden;
+            if ui.checkbox(&mut shown, "Instanced Torus Field").changed() {
+                *visibility = if shown {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+        ui.separator();
+        camera_mode_ui(ui, &mut camera_mode);
+        ui.separator();
+        camera_path_editor.ui(ui, &mut camera_mode);
+        ui.separator();
+        glow_ui(ui, &mut glow);
+        ui.separator();
+        crt_editor.ui(ui);
+        ui.separator();
+        fog_editor.ui(ui, &mut shader_settings.fog_mode);
+        ui.separator();
+        hologram_editor.ui(ui);
+        ui.separator();
+        backdrop_editor.ui(ui);
         ui.separator();
         ui.heading("Color Source");
 
@@ -554,74 +799,138 @@ fn ui_system(
         ui.checkbox(&mut shader_settings.show_wireframe, "Show Wireframe");
         ui.checkbox(&mut shader_settings.show_outline, "Show Outline");
         ui.checkbox(&mut shader_settings.show_fill, "Show Fill");
+        let opacity = &mut shader_settings.opacity;
+        ui.add(egui::Slider::new(&mut opacity.wireframe, 0.0..=1.0).text("Wireframe Opacity"));
+        ui.add(egui::Slider::new(&mut opacity.outline, 0.0..=1.0).text("Outline Opacity"));
+        ui.add(egui::Slider::new(&mut opacity.fill, 0.0..=1.0).text("Fill Opacity"));
+        ui.add(egui::Slider::new(&mut opacity.fade_time, 0.0..=2.0).text("Fade Time"));
+        let tiers = &mut shader_settings.edge_tiers;
+        for tier in EdgeTier::ALL {
+            let index = tier.index() as usize;
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut tiers.visible[index], format!("{:?} Edges", tier));
+                ui.add(egui::Slider::new(&mut tiers.brightness[index], 0.0..=3.0));
+            });
+        }
+        line_lods.ui(ui);
     });
+}
+
+// Moves the fade of each mesh's layers towards their opacity, or 0 while the layer isn't shown for
+// that mesh, and hides layer children that have faded out completely
+fn fade_layers(
+    time: Res<Time>,
+    shader_settings: Res<ShaderSettings>,
+    layers: LayersLookup,
+    mut meshes: Query<(Entity, &mut MeshLayers)>,
+    mut visibilities: Query<&mut Visibility>,
+) {
+    let opacity = shader_settings.opacity;
+    let step = if opacity.fade_time > 0.0 {
+        time.delta_seconds() / opacity.fade_time
+    } else {
+        f32::INFINITY
+    };
+
+    for (entity, mut mesh_layers) in meshes.iter_mut() {
+        let shown = shown_layers(&shader_settings, layers.get(entity), mesh_layers.source);
+        let mesh_layers = &mut *mesh_layers;
+        let slots = [
+            (&mut mesh_layers.fill, shown.fill, opacity.fill),
+            (&mut mesh_layers.outline, shown.outline, opacity.outline),
+            (&mut mesh_layers.wireframe, shown.wireframe, opacity.wireframe),
+        ];
+        for (slot, shown, opacity) in slots {
+            let target = if shown { opacity.clamp(0.0, 1.0) } else { 0.0 };
+            slot.fade_towards(target, step);
+
+            let Some(mut visibility) = slot.entity.and_then(|child| visibilities.get_mut(child).ok()) else {
+                continue;
+            };
+            visibility.set_if_neq(if slot.fade > 0.0 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct LayerMaterials<'w, 's> {
+    outline_materials_assets: ResMut<'w, Assets<OutlineMaterial>>,
+    outline_materials: Query<'w, 's, (Entity, &'static Handle<OutlineMaterial>)>,
+    line_materials_assets: ResMut<'w, Assets<LineMaterial>>,
+    line_materials: Query<'w, 's, (Entity, &'static Handle<LineMaterial>)>,
+    fill_materials_assets: ResMut<'w, Assets<FillMaterial>>,
+    fill_materials: Query<'w, 's, (Entity, &'static Handle<FillMaterial>)>,
+    parents: Query<'w, 's, &'static Parent>,
+    mesh_layers: Query<'w, 's, &'static MeshLayers>,
+}
+
+impl LayerMaterials<'_, '_> {
+    // the fade of the MeshLayers slot a layer child belongs to, `opacity` for materials outside them
+    fn fade(&self, child: Entity, opacity: f32) -> f32 {
+        self.parents
+            .get(child)
+            .ok()
+            .and_then(|parent| self.mesh_layers.get(parent.get()).ok())
+            .and_then(|mesh_layers| mesh_layers.fade_of(child))
+            .unwrap_or(opacity)
+    }
+}
+
+// Copies the settings into every layer material, also the ones spawned since the last change
+fn update_layer_materials(shader_settings: Res<ShaderSettings>, mut materials: LayerMaterials) {
+    let opacity = shader_settings.opacity;
 
     // Update all OutlineMaterial instances
-    for material_handle in outline_materials.iter() {
-        if let Some(material) = outline_materials_assets.get_mut(material_handle) {
+    for (entity, material_handle) in materials.outline_materials.iter() {
+        let fade = materials.fade(entity, opacity.outline);
+        if let Some(material) = materials.outline_materials_assets.get_mut(material_handle) {
             material.outline_width = shader_settings.outline_width;
             material.brightness = shader_settings.brightness;
             material.vertex_color_mode = shader_settings.vertex_color_mode;
             material.color = shader_settings.color.to_linear().to_vec4();
-            material.visibility = if shader_settings.show_outline {
-                1.0
-            } else {
-                0.0
-            };
+            material.fog_mode = shader_settings.fog_mode;
+            material.opacity = fade;
         }
     }
 
     // Update all LineMaterial instances
-    for material_handle in line_materials.iter() {
-        if let Some(material) = line_materials_assets.get_mut(material_handle) {
+    for (entity, material_handle) in materials.line_materials.iter() {
+        let fade = materials.fade(entity, opacity.wireframe);
+        if let Some(material) = materials.line_materials_assets.get_mut(material_handle) {
             material.displacement = shader_settings.wireframe_displacement;
             material.brightness = shader_settings.brightness;
             material.vertex_color_mode = shader_settings.vertex_color_mode;
             material.color = shader_settings.color.to_linear().to_vec4();
-            material.visibility = if shader_settings.show_wireframe {
-                1.0
-            } else {
-                0.0
-            };
+            material.fog_mode = shader_settings.fog_mode;
+            material.opacity = fade;
+            let tiers = &shader_settings.edge_tiers;
+            material.tier_brightness = Vec4::new(tiers.brightness[0], tiers.brightness[1], tiers.brightness[2], 0.0);
+            material.tier_visibility = Vec4::new(
+                tiers.visible[0] as u8 as f32,
+                tiers.visible[1] as u8 as f32,
+                tiers.visible[2] as u8 as f32,
+                0.0,
+            );
         }
     }
 
     // Update all FillMaterial instances
-    for material_handle in fill_materials.iter() {
-        if let Some(material) = fill_materials_assets.get_mut(material_handle) {
+    for (entity, material_handle) in materials.fill_materials.iter() {
+        let fade = materials.fade(entity, opacity.fill);
+        if let Some(material) = materials.fill_materials_assets.get_mut(material_handle) {
             material.displacement = shader_settings.fill_displacement;
             material.shininess = shader_settings.fill_shininess;
             material.specular_strength = shader_settings.fill_specular_strength;
             material.vertex_color_mode = shader_settings.vertex_color_mode;
             material.color = shader_settings.color.to_linear().to_vec4();
-            material.visibility = if shader_settings.show_fill { 1.0 } else { 0.0 };
+            material.fog_mode = shader_settings.fog_mode;
+            material.opacity = fade;
         }
     }
-
-    // Update visibility
-    for mut visibility in visibility_set.p2().iter_mut() {
-        *visibility = if shader_settings.show_wireframe {
-            Visibility::Inherited
-        } else {
-            Visibility::Hidden
-        };
-    }
-
-    for mut visibility in visibility_set.p1().iter_mut() {
-        *visibility = if shader_settings.show_outline {
-            Visibility::Inherited
-        } else {
-            Visibility::Hidden
-        };
-    }
-
-    for mut visibility in visibility_set.p0().iter_mut() {
-        *visibility = if shader_settings.show_fill {
-            Visibility::Inherited
-        } else {
-            Visibility::Hidden
-        };
-    }
 }
 
 fn update_scene_visibility(
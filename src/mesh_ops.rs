@@ -8,56 +8,44 @@ use bevy::{
     render::{
         mesh::{Indices, VertexAttributeValues},
         render_asset::RenderAssetUsages,
+        render_resource::PrimitiveTopology,
     },
     utils::{HashMap, HashSet},
 };
 
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use tracing::{info, warn};
 
-use crate::{ATTRIBUTE_SMOOTHED_NORMAL, ATTRIBUTE_VERT_INDEX};
+use crate::parse_extras::{EdgeTier, JsonLineList};
+use crate::{
+    ATTRIBUTE_EDGE_TIER, ATTRIBUTE_SMOOTHED_NORMAL, ATTRIBUTE_VERT_INDEX_HI_LAYERS, ATTRIBUTE_VERT_INDEX_LAYERS,
+    ATTRIBUTE_VERT_INDEX_U32_LAYERS, EDGE_AUTHORED_COLOR, VERT_INDEX_LAYER_COUNT,
+};
 // use crate::{ATTRIBUTE_ALT_COLOR};
 
 
-fn apply_random_vertex_colors(mesh: &mut Mesh) {
-    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-    let mut unique_positions: Vec<([f32; 3], [f32; 4])> = Vec::new();
-
-    let mult: f32 = 20.0;
+/// Default seed for the random ops on a glTF primitive: FNV-1a over the mesh asset path and
+/// primitive index, so every run and every instance of a scene gets the same result.
+pub fn mesh_seed(mesh_path: &str, primitive: usize) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
 
-    if let Some(VertexAttributeValues::Float32x3(positions)) =
-        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
-    {
-        let colors: Vec<[f32; 4]> = positions
-            .iter()
-            .map(|&pos| {
-                match unique_positions
-                    .iter()
-                    .position(|&(p, _)| vec3_approx_eq(p, pos))
-                {
-                    Some(index) => unique_positions[index].1,
-                    None => {
-                        let color = [
-                            rng.gen::<f32>() * mult,
-                            rng.gen::<f32>() * mult,
-                            rng.gen::<f32>() * mult,
-                            1.0,
-                        ];
-
-                        unique_positions.push((pos, color));
-                        color
-                    }
-                }
-            })
-            .collect();
-        // mesh.insert_attribute(ATTRIBUTE_ALT_COLOR, colors);
-        // mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh_path
+        .bytes()
+        .chain((primitive as u64).to_le_bytes())
+        .fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
 
+fn apply_random_vertex_colors(mesh: &mut Mesh, seed: u64) {
+    match generate_random_vertex_colors(mesh, seed) {
+        Ok(colors) => mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors),
+        Err(e) => warn!("unable to randomize vertex colors: {}", e),
     }
 }
 
-pub fn generate_random_vertex_colors(mesh: &Mesh) -> Result<Vec<[f32; 4]> >{
-    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
+pub fn generate_random_vertex_colors(mesh: &Mesh, seed: u64) -> Result<Vec<[f32; 4]> >{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
     let mut unique_positions: Vec<([f32; 3], [f32; 4])> = Vec::new();
 
     // let mult: f32 = 20.0;
@@ -97,9 +85,248 @@ pub fn generate_random_vertex_colors(mesh: &Mesh) -> Result<Vec<[f32; 4]> >{
 }
 
 
+// Blender exports `_VERT_INDEX` as a float attribute, which can only hold integers exactly up to 2^24
+const MAX_EXACT_F32_INDEX: u32 = 1 << 24;
+// `_VERT_INDEX_HI` holds what's left of a u32 id above those 24 bits
+const MAX_HIGH_INDEX: u32 = 1 << 8;
+
+/// Parses an index layer attribute name such as `VERT_INDEX.002` or `_VERT_INDEX_U32.002`
+/// into its layer number.
+pub fn vert_index_layer_from_name(name: &str) -> Option<usize> {
+    let name = name.trim_start_matches('_');
+    let suffix = name
+        .strip_prefix("VERT_INDEX_U32")
+        .or_else(|| name.strip_prefix("VERT_INDEX"))?;
+    layer_from_suffix(suffix)
+}
+
+/// Parses the high half of an index layer, `_VERT_INDEX_HI` or `_VERT_INDEX_HI.00N`.
+pub fn vert_index_hi_layer_from_name(name: &str) -> Option<usize> {
+    layer_from_suffix(name.trim_start_matches('_').strip_prefix("VERT_INDEX_HI")?)
+}
+
+fn layer_from_suffix(suffix: &str) -> Option<usize> {
+    let layer = match suffix {
+        "" => 0,
+        _ => suffix.strip_prefix('.')?.parse().ok()?,
+    };
+
+    (layer < VERT_INDEX_LAYER_COUNT).then_some(layer)
+}
+
+/// Lists the `VERT_INDEX` layers present on the mesh, in either float or integer form.
+pub fn vert_index_layers(mesh: &Mesh) -> Vec<usize> {
+    (0..VERT_INDEX_LAYER_COUNT)
+        .filter(|&layer| {
+            mesh.contains_attribute(ATTRIBUTE_VERT_INDEX_U32_LAYERS[layer].id)
+                || mesh.contains_attribute(ATTRIBUTE_VERT_INDEX_LAYERS[layer].id)
+        })
+        .collect()
+}
+
+/// Reads the Blender vertex id of every vertex in the mesh for one index layer.
+///
+/// The integer `VERT_INDEX_U32` attribute is preferred. Otherwise the float `VERT_INDEX` holds the
+/// low 24 bits and `VERT_INDEX_HI` the rest, files from older exports only have the former with
+/// the whole id, which is accepted if every value is a whole number that f32 can represent exactly.
+pub fn vert_indices(mesh: &Mesh, layer: usize) -> Result<Vec<u32>> {
+    if layer >= VERT_INDEX_LAYER_COUNT {
+        return Err(anyhow!("VERT_INDEX layer {} is not supported", layer));
+    }
+
+    let integer = &ATTRIBUTE_VERT_INDEX_U32_LAYERS[layer];
+    if let Some(attr) = mesh.attribute(integer.id) {
+        return match attr {
+            VertexAttributeValues::Uint32(values) => Ok(values.clone()),
+            _ => Err(anyhow!("{}: invalid attribute format", integer.name)),
+        };
+    }
+
+    let float = &ATTRIBUTE_VERT_INDEX_LAYERS[layer];
+    let low = match mesh.attribute(float.id) {
+        Some(VertexAttributeValues::Float32(values)) => values,
+        Some(_) => return Err(anyhow!("{}: invalid attribute format", float.name)),
+        None => return Err(anyhow!("mesh has no {} attribute", float.name)),
+    };
+
+    let high = &ATTRIBUTE_VERT_INDEX_HI_LAYERS[layer];
+    match mesh.attribute(high.id) {
+        Some(VertexAttributeValues::Float32(high)) => vert_indices_from_f32_pair(low, high),
+        Some(_) => Err(anyhow!("{}: invalid attribute format", high.name)),
+        None => vert_indices_from_f32(low),
+    }
+}
+
+// the float as a whole number below `limit`, which f32 holds exactly as long as limit <= 2^24
+fn exact_index(value: f32, limit: u32) -> Option<u32> {
+    (value >= 0.0 && value.fract() == 0.0 && value < limit as f32).then_some(value as u32)
+}
+
+pub fn vert_indices_from_f32(values: &[f32]) -> Result<Vec<u32>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(vertex, &value)| {
+            exact_index(value, MAX_EXACT_F32_INDEX).ok_or_else(|| {
+                anyhow!(
+                    "VERT_INDEX {} of vertex {} is not exactly representable as f32, re-export with the current Blender script, which adds VERT_INDEX_HI",
+                    value,
+                    vertex
+                )
+            })
+        })
+        .collect()
+}
+
+/// Joins the low 24 bits from `VERT_INDEX` and the high 8 from `VERT_INDEX_HI` into the ids.
+pub fn vert_indices_from_f32_pair(low: &[f32], high: &[f32]) -> Result<Vec<u32>> {
+    if low.len() != high.len() {
+        return Err(anyhow!(
+            "VERT_INDEX has {} values but VERT_INDEX_HI {}",
+            low.len(),
+            high.len()
+        ));
+    }
+
+    low.iter()
+        .zip(high)
+        .enumerate()
+        .map(|(vertex, (&low, &high))| {
+            match (exact_index(low, MAX_EXACT_F32_INDEX), exact_index(high, MAX_HIGH_INDEX)) {
+                (Some(low), Some(high)) => Ok(high << 24 | low),
+                _ => Err(anyhow!(
+                    "VERT_INDEX {} / VERT_INDEX_HI {} of vertex {} is not a valid id",
+                    low,
+                    high,
+                    vertex
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Converts every float `VERT_INDEX` layer into its integer `VERT_INDEX_U32` counterpart.
+///
+/// Must run before `duplicate_vertices` or any other rebuild so the ids travel with the vertices.
+fn store_vert_indices(mesh: &mut Mesh) -> Result<()> {
+    let layers = vert_index_layers(mesh);
+    if layers.is_empty() {
+        return Err(anyhow!("mesh has no VERT_INDEX attribute"));
+    }
+
+    for layer in layers {
+        let indices = vert_indices(mesh, layer)?;
+        mesh.insert_attribute(ATTRIBUTE_VERT_INDEX_U32_LAYERS[layer].clone(), indices);
+    }
+    Ok(())
+}
+
+/// Lookup from Blender vertex id to the index of a vertex carrying that id in the current layout.
+///
+/// Duplicated vertices share an id, in which case the first one is used.
+#[derive(Debug, Clone, Default)]
+pub struct VertIndexMap {
+    vertices: HashMap<u32, u32>,
+}
+
+impl VertIndexMap {
+    /// Exports that write `VERT_INDEX_HI` number vertices from 1 and leave 0 on vertices joined in
+    /// from other objects, older ones number from 0.
+    pub fn from_mesh(mesh: &Mesh, layer: usize) -> Result<Self> {
+        let zero_is_unset = mesh.contains_attribute(ATTRIBUTE_VERT_INDEX_HI_LAYERS[layer].id);
+        Ok(Self::from_indices(&vert_indices(mesh, layer)?, zero_is_unset))
+    }
+
+    pub fn from_indices(indices: &[u32], zero_is_unset: bool) -> Self {
+        let mut vertices = HashMap::with_capacity(indices.len());
+        for (vertex, &index) in indices.iter().enumerate() {
+            if index == 0 && zero_is_unset {
+                continue;
+            }
+            vertices.entry(index).or_insert(vertex as u32);
+        }
+        Self { vertices }
+    }
+
+    pub fn get(&self, index: u32) -> Option<u32> {
+        self.vertices.get(&index).copied()
+    }
+}
+
+/// The `VertIndexMap` of every index layer present on a mesh.
+#[derive(Debug, Clone, Default)]
+pub struct VertIndexLayers {
+    layers: Vec<(usize, VertIndexMap)>,
+}
+
+impl VertIndexLayers {
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self> {
+        let layers = vert_index_layers(mesh)
+            .into_iter()
+            .map(|layer| Ok((layer, VertIndexMap::from_mesh(mesh, layer)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        if layers.is_empty() {
+            return Err(anyhow!("mesh has no VERT_INDEX attribute"));
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Resolves both ends of an edge against one index layer. Every layer numbers its vertices
+    /// from the same start, so trying the others would connect the wrong vertices, and edges
+    /// without an explicit layer only resolve against `_VERT_INDEX`.
+    pub fn resolve_edge(&self, edge: [u32; 2], layer: Option<usize>) -> Option<(u32, u32)> {
+        let layer = layer.unwrap_or(0);
+        let (_, map) = self.layers.iter().find(|(l, _)| *l == layer)?;
+        Some((map.get(edge[0])?, map.get(edge[1])?))
+    }
+}
+
+pub trait VertexIdentity {
+    fn store_vert_indices(&mut self) -> Result<()>;
+    fn vert_index_layers(&self) -> Result<VertIndexLayers>;
+}
+
+impl VertexIdentity for Mesh {
+    fn store_vert_indices(&mut self) -> Result<()> {
+        store_vert_indices(self)
+    }
+    fn vert_index_layers(&self) -> Result<VertIndexLayers> {
+        VertIndexLayers::from_mesh(self)
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct LineList {
-    pub lines: Vec<(Vert, Vert)>,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Line {
+    pub start: Vert,
+    pub end: Vert,
+    pub tier: EdgeTier,
+    // authored color of the whole line, replaces the vertex colors of both ends
+    pub color: Option<[f32; 4]>,
+}
+
+impl Line {
+    pub fn new(start: Vert, end: Vert) -> Self {
+        Self {
+            start,
+            end,
+            tier: EdgeTier::default(),
+            color: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -112,12 +339,12 @@ pub struct Vert {
 }
 
 pub trait MeshToLineList {
-    fn mesh_to_line_list_from_json(&self, data: &crate::JsonLineList) -> LineList;
+    fn mesh_to_line_list_from_json(&self, data: &JsonLineList) -> LineList;
     fn mesh_to_line_list(&self) -> LineList;
 }
 
 impl MeshToLineList for Mesh {
-    fn mesh_to_line_list_from_json(&self, data: &crate::JsonLineList) -> LineList {
+    fn mesh_to_line_list_from_json(&self, data: &JsonLineList) -> LineList {
         mesh_to_line_list_from_json(self, data)
     }
     fn mesh_to_line_list(&self) -> LineList {
@@ -130,15 +357,15 @@ impl MeshToLineList for Mesh {
 
 pub trait VertexOps {
     fn smooth_normals_non_indexed(&mut self);
-    fn randomize_vertex_colors(&mut self);
+    fn randomize_vertex_colors(&mut self, seed: u64);
 }
 
 impl VertexOps for Mesh {
     fn smooth_normals_non_indexed(&mut self) {
         smooth_normals_non_indexed(self);
     }
-    fn randomize_vertex_colors(&mut self) {
-        apply_random_vertex_colors(self);
+    fn randomize_vertex_colors(&mut self, seed: u64) {
+        apply_random_vertex_colors(self, seed);
     }
 }
 
@@ -151,16 +378,19 @@ pub fn line_list_to_mesh(line_list: &LineList, mesh: &Mesh) -> Mesh {
     let positions: Vec<[f32; 3]> = line_list
         .lines
         .iter()
-        .flat_map(|(start, end)| vec![start.position, end.position])
+        .flat_map(|line| vec![line.start.position, line.end.position])
         .collect();
 
     new_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
 
+    // the line color, then the vertex colors, white for ends with neither so every vertex gets
+    // one and the attribute stays aligned with the positions
     let colors: Vec<[f32; 4]> = line_list
         .lines
         .iter()
-        .flat_map(|(start, end)| vec![start.color, end.color])
-        .flatten()
+        .flat_map(|line| {
+            [line.start.color, line.end.color].map(|color| line.color.or(color).unwrap_or([1.0; 4]))
+        })
         .collect();
 
     new_mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
@@ -168,16 +398,27 @@ pub fn line_list_to_mesh(line_list: &LineList, mesh: &Mesh) -> Mesh {
     let normal: Vec<[f32; 3]> = line_list
         .lines
         .iter()
-        .flat_map(|(start, end)| vec![start.normal, end.normal])
+        .flat_map(|line| vec![line.start.normal, line.end.normal])
         .collect();
 
     new_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normal);
 
+    let tiers: Vec<f32> = line_list
+        .lines
+        .iter()
+        .flat_map(|line| {
+            let authored = if line.color.is_some() { EDGE_AUTHORED_COLOR } else { 0 };
+            [(line.tier.index() | authored) as f32; 2]
+        })
+        .collect();
+
+    new_mesh.insert_attribute(ATTRIBUTE_EDGE_TIER, tiers);
+
     if let Some(VertexAttributeValues::Uint16x4(_)) = mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX) {
         let joint_indices: Vec<[u16; 4]> = line_list
             .lines
             .iter()
-            .flat_map(|(start, end)| vec![start.joint_indices, end.joint_indices])
+            .flat_map(|line| vec![line.start.joint_indices, line.end.joint_indices])
             .flatten()
             .collect();
         new_mesh.insert_attribute(
@@ -191,7 +432,7 @@ pub fn line_list_to_mesh(line_list: &LineList, mesh: &Mesh) -> Mesh {
         let joint_weights: Vec<[f32; 4]> = line_list
             .lines
             .iter()
-            .flat_map(|(start, end)| vec![start.joint_weights, end.joint_weights])
+            .flat_map(|line| vec![line.start.joint_weights, line.end.joint_weights])
             .flatten()
             .collect();
         new_mesh.insert_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, joint_weights);
@@ -199,7 +440,115 @@ pub fn line_list_to_mesh(line_list: &LineList, mesh: &Mesh) -> Mesh {
 
     new_mesh
 }
-fn mesh_to_line_list_from_json(input_mesh: &Mesh, data: &crate::JsonLineList) -> LineList {
+
+/// Crease angles in degrees the coarser wireframe LODs are cut at, see `line_list_lods`.
+pub const LINE_LOD_CREASE_ANGLES: [f32; 2] = [25.0, 60.0];
+
+/// `line_list` followed by coarser copies that only keep the edges whose faces meet at
+/// `crease_angles` degrees or more. Boundary edges, edges without a face in `mesh` and primary
+/// edges stay in all of them, detail edges only in the first. A LOD that drops no lines is
+/// skipped, and it stops before one that drops all of them.
+pub fn line_list_lods(line_list: &LineList, mesh: &Mesh, crease_angles: &[f32]) -> Vec<LineList> {
+    let creases = match edge_crease_angles(mesh) {
+        Ok(creases) => creases,
+        Err(e) => {
+            warn!("no line LODs: {}", e);
+            return vec![line_list.clone()];
+        }
+    };
+    let angles: Vec<f32> = line_list
+        .lines
+        .iter()
+        .map(|line| {
+            let edge = edge_key(&line.start.position, &line.end.position);
+            creases.get(&edge).copied().unwrap_or(180.0)
+        })
+        .collect();
+
+    let mut lods = vec![line_list.clone()];
+    for &threshold in crease_angles {
+        let lines: Vec<Line> = line_list
+            .lines
+            .iter()
+            .zip(&angles)
+            .filter(|(line, &angle)| match line.tier {
+                EdgeTier::Primary => true,
+                EdgeTier::Secondary => angle >= threshold,
+                EdgeTier::Detail => false,
+            })
+            .map(|(line, _)| line.clone())
+            .collect();
+        if lines.is_empty() {
+            break;
+        }
+        if lods.last().is_some_and(|lod| lod.lines.len() == lines.len()) {
+            continue;
+        }
+        lods.push(LineList { lines });
+    }
+    lods
+}
+
+type EdgeKey = ((i32, i32, i32), (i32, i32, i32));
+
+fn edge_key(a: &[f32; 3], b: &[f32; 3]) -> EdgeKey {
+    let (a, b) = (position_key(a), position_key(b));
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Angle between the faces either side of every welded edge, 180 for boundary edges
+fn edge_crease_angles(mesh: &Mesh) -> Result<HashMap<EdgeKey, f32>> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return Err(anyhow!("not a triangle mesh"));
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Err(anyhow!("mesh has no positions"));
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    let mut face_normals: HashMap<EdgeKey, Vec<Vec3>> = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| Vec3::from(positions[i]));
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        // degenerate triangles have no side to crease against
+        if normal == Vec3::ZERO {
+            continue;
+        }
+        for (v1, v2) in [(0, 1), (1, 2), (2, 0)] {
+            face_normals
+                .entry(edge_key(&positions[triangle[v1]], &positions[triangle[v2]]))
+                .or_default()
+                .push(normal);
+        }
+    }
+    if face_normals.is_empty() {
+        return Err(anyhow!("mesh has no triangles"));
+    }
+
+    Ok(face_normals
+        .into_iter()
+        .map(|(edge, normals)| {
+            // the sharpest pair, for the odd edge shared by more than two faces
+            let mut angle: f32 = if normals.len() < 2 { 180.0 } else { 0.0 };
+            for (i, n1) in normals.iter().enumerate() {
+                for n2 in &normals[i + 1..] {
+                    angle = angle.max(n1.angle_between(*n2).to_degrees());
+                }
+            }
+            (edge, angle)
+        })
+        .collect())
+}
+
+fn mesh_to_line_list_from_json(input_mesh: &Mesh, data: &JsonLineList) -> LineList {
     
     let mut line_list = LineList::default();
     let mut edge_set = HashSet::new();
@@ -287,56 +636,63 @@ fn mesh_to_line_list_from_json(input_mesh: &Mesh, data: &crate::JsonLineList) ->
 
 
 
-        let index_vec = input_mesh.attribute(ATTRIBUTE_VERT_INDEX).and_then(|attr| {
-
-            if let VertexAttributeValues::Float32(values) = attr {
-                // info!("found {} ATTRIBUTE_VERT_INDEX values", values.len());
-                Some(values)
-            } else {
-                warn!("unable to get ATTRIBUTE_VERT_INDEX attribute");
-                None
+        // Map Blender vertex ids to vertices in the current (possibly duplicated) layout
+        let index_layers = match input_mesh.vert_index_layers() {
+            Ok(layers) => layers,
+            Err(e) => {
+                warn!("unable to resolve VERT_INDEX: {}", e);
+                VertIndexLayers::default()
             }
-        });
-
-        if index_vec.is_none() {
-            // panic!("ATTRIBUTE_VERT_INDEX: invalid attribute");
-        }
+        };
 
-        // Create a mapping from INDEX values to vertex indices
-        let mut index_to_vertex = HashMap::new();
-        if let Some(index_values) = &index_vec {
-            for (vertex_index, &index_value) in index_values.iter().enumerate() {
-                index_to_vertex.insert(index_value as u32, vertex_index as u32);
-            }
+        // exports from before index layers existed only know `_VERT_INDEX`
+        if index_layers.len() > 1 && data.edge_sets().all(|edges| edges.index_layer.is_none()) {
+            warn!(
+                "edges have no indexLayer, only _VERT_INDEX of {} index layers is used, re-export with the current Blender script to keep the edges of joined objects",
+                index_layers.len()
+            );
         }
 
         // Process the JSON line list
-        for &[index1, index2] in &data.line_list {
-            if let (Some(&v1), Some(&v2)) =
-                (index_to_vertex.get(&index1), index_to_vertex.get(&index2))
-            {
-                let edge = if v1 < v2 { (v1, v2) } else { (v2, v1) };
-                if edge_set.insert(edge) {
-                    let i1 = v1 as usize;
-                    let i2 = v2 as usize;
-                    let start = Vert {
-                        position: positions[i1],
-                        normal: normals[i1],
-                        color: colors.map(|c| c[i1]),
-                        joint_indices: joint_indices.map(|ji| ji[i1]),
-                        joint_weights: joint_weights.map(|jw| jw[i1]),
-                    };
-                    let end = Vert {
-                        position: positions[i2],
-                        normal: normals[i2],
-                        color: colors.map(|c| c[i2]),
-                        joint_indices: joint_indices.map(|ji| ji[i2]),
-                        joint_weights: joint_weights.map(|jw| jw[i2]),
-                    };
-                    line_list.lines.push((start, end));
+        for edges in data.edge_sets() {
+            for mismatch in edges.color_mismatches() {
+                warn!("edge colors (index layer {:?}): {}", edges.index_layer, mismatch);
+            }
+            for json_edge in edges.tagged_edges() {
+                let [index1, index2] = json_edge.vertices;
+                if let Some((v1, v2)) = index_layers.resolve_edge([index1, index2], edges.index_layer)
+                {
+                    let edge = if v1 < v2 { (v1, v2) } else { (v2, v1) };
+                    if edge_set.insert(edge) {
+                        let i1 = v1 as usize;
+                        let i2 = v2 as usize;
+                        let start = Vert {
+                            position: positions[i1],
+                            normal: normals[i1],
+                            color: colors.map(|c| c[i1]),
+                            joint_indices: joint_indices.map(|ji| ji[i1]),
+                            joint_weights: joint_weights.map(|jw| jw[i1]),
+                        };
+                        let end = Vert {
+                            position: positions[i2],
+                            normal: normals[i2],
+                            color: colors.map(|c| c[i2]),
+                            joint_indices: joint_indices.map(|ji| ji[i2]),
+                            joint_weights: joint_weights.map(|jw| jw[i2]),
+                        };
+                        line_list.lines.push(Line {
+                            start,
+                            end,
+                            tier: json_edge.tier,
+                            color: json_edge.color,
+                        });
+                    }
+                } else {
+                    warn!(
+                        "Warning: INDEX {} or {} not found in mesh (index layer {:?})",
+                        index1, index2, edges.index_layer
+                    );
                 }
-            } else {
-                warn!("Warning: INDEX {} or {} not found in mesh", index1, index2);
             }
         }
     }
@@ -403,7 +759,7 @@ fn mesh_to_line_list(mesh: &Mesh) -> Result<LineList> {
                         joint_indices: joint_indices.map(|ji| ji[v2]),
                         joint_weights: joint_weights.map(|jw| jw[v2]),
                     };
-                    line_list.lines.push((start, end));
+                    line_list.lines.push(Line::new(start, end));
                 }
             };
 
@@ -495,6 +851,153 @@ fn smooth_normals_non_indexed(mesh: &mut Mesh) {
     }
 }
 
+/// How `COLOR_0` is generated for meshes exported without vertex colors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorizeStrategy {
+    Constant([f32; 4]),
+    /// Random color per welded vertex, so split normals and UV seams share a color
+    RandomPerVertex,
+    RandomPerMesh,
+    /// Blends from `bottom` to `top` over the mesh's local height
+    HeightGradient { bottom: [f32; 4], top: [f32; 4] },
+    /// Random color per group of triangles connected through shared positions
+    ConnectedComponent,
+}
+
+impl Default for ColorizeStrategy {
+    fn default() -> Self {
+        ColorizeStrategy::Constant([1.0, 1.0, 1.0, 1.0])
+    }
+}
+
+/// Fills in `COLOR_0` with the strategy when the mesh has none. Returns whether colors were added.
+pub fn ensure_vertex_colors(mesh: &mut Mesh, strategy: &ColorizeStrategy, seed: u64) -> Result<bool> {
+    if mesh.contains_attribute(Mesh::ATTRIBUTE_COLOR) {
+        return Ok(false);
+    }
+    colorize_vertex_colors(mesh, strategy, seed)?;
+    Ok(true)
+}
+
+/// Replaces `COLOR_0` with colors generated by the strategy. The same seed always gives the same colors.
+pub fn colorize_vertex_colors(mesh: &mut Mesh, strategy: &ColorizeStrategy, seed: u64) -> Result<()> {
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return Err(anyhow!("mesh has no positions"));
+    };
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let colors: Vec<[f32; 4]> = match strategy {
+        ColorizeStrategy::Constant(color) => vec![*color; positions.len()],
+        ColorizeStrategy::RandomPerMesh => vec![random_color(&mut rng); positions.len()],
+        ColorizeStrategy::RandomPerVertex => {
+            let mut welded: HashMap<(i32, i32, i32), [f32; 4]> = HashMap::new();
+            positions
+                .iter()
+                .map(|pos| *welded.entry(position_key(pos)).or_insert_with(|| random_color(&mut rng)))
+                .collect()
+        }
+        ColorizeStrategy::HeightGradient { bottom, top } => {
+            let (min, max) = positions
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), pos| (min.min(pos[1]), max.max(pos[1])));
+            let height = (max - min).max(f32::EPSILON);
+            positions
+                .iter()
+                .map(|pos| {
+                    let t = (pos[1] - min) / height;
+                    std::array::from_fn(|i| bottom[i] + (top[i] - bottom[i]) * t)
+                })
+                .collect()
+        }
+        ColorizeStrategy::ConnectedComponent => {
+            let components = connected_components(mesh)?;
+            let mut component_colors: HashMap<u32, [f32; 4]> = HashMap::new();
+            components
+                .iter()
+                .map(|component| *component_colors.entry(*component).or_insert_with(|| random_color(&mut rng)))
+                .collect()
+        }
+    };
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    Ok(())
+}
+
+fn random_color(rng: &mut impl Rng) -> [f32; 4] {
+    [rng.gen::<f32>(), rng.gen::<f32>(), rng.gen::<f32>(), 1.0]
+}
+
+// Same 1/1000 unit grid the normal smoothing welds positions with
+fn position_key(pos: &[f32; 3]) -> (i32, i32, i32) {
+    (
+        (pos[0] * 1000.0).round() as i32,
+        (pos[1] * 1000.0).round() as i32,
+        (pos[2] * 1000.0).round() as i32,
+    )
+}
+
+// Component id of every vertex, joining triangles that share a welded position.
+// Works on indexed and de-indexed meshes alike.
+fn connected_components(mesh: &Mesh) -> Result<Vec<u32>> {
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return Err(anyhow!("mesh has no positions"));
+    };
+
+    // union-find over welded positions
+    let mut welded: HashMap<(i32, i32, i32), u32> = HashMap::new();
+    let vertex_roots: Vec<u32> = positions
+        .iter()
+        .map(|pos| {
+            let next = welded.len() as u32;
+            *welded.entry(position_key(pos)).or_insert(next)
+        })
+        .collect();
+    let mut parents: Vec<u32> = (0..welded.len() as u32).collect();
+
+    fn find(parents: &mut [u32], mut node: u32) -> u32 {
+        while parents[node as usize] != node {
+            parents[node as usize] = parents[parents[node as usize] as usize];
+            node = parents[node as usize];
+        }
+        node
+    }
+
+    let triangles: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+    for triangle in triangles.chunks_exact(3) {
+        let a = find(&mut parents, vertex_roots[triangle[0]]);
+        for &vertex in &triangle[1..] {
+            let b = find(&mut parents, vertex_roots[vertex]);
+            parents[b as usize] = a;
+        }
+    }
+
+    Ok(vertex_roots
+        .iter()
+        .map(|&root| find(&mut parents, root))
+        .collect())
+}
+
+/// Prepares an imported triangle mesh for the fill, outline and wireframe layers.
+///
+/// Stores smoothed normals for the outline and exact vertex ids for the edge data, then
+/// de-indexes the mesh with flat normals for the fill.
+pub fn prepare_vaporwave_mesh(mesh: &mut Mesh) -> Result<()> {
+    let smoothed_normals = get_smoothed_normals(mesh)?;
+    mesh.insert_attribute(ATTRIBUTE_SMOOTHED_NORMAL, smoothed_normals);
+
+    // store the Blender ids as integers so they survive duplicate_vertices exactly
+    if let Err(e) = store_vert_indices(mesh) {
+        warn!("unable to store VERT_INDEX: {}", e);
+    }
+
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+    Ok(())
+}
+
 pub fn get_smoothed_normals(mesh: &mut Mesh) -> Result<Vec<[f32; 3]>> {
     if let (
         Some(VertexAttributeValues::Float32x3(positions)),
@@ -558,4 +1061,126 @@ impl AsFloat4 for VertexAttributeValues {
             _ => None,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f32_ids_are_exact_below_2_pow_24() {
+        assert_eq!(vert_indices_from_f32(&[0.0, 1.0, 16_777_215.0]).unwrap(), vec![0, 1, 16_777_215]);
+        assert!(vert_indices_from_f32(&[16_777_216.0]).is_err());
+        assert!(vert_indices_from_f32(&[-1.0]).is_err());
+        assert!(vert_indices_from_f32(&[2.5]).is_err());
+    }
+
+    #[test]
+    fn f32_pairs_hold_full_u32_ids() {
+        let ids = vert_indices_from_f32_pair(&[0.0, 5.0, 16_777_215.0], &[0.0, 1.0, 255.0]).unwrap();
+        assert_eq!(ids, vec![0, (1 << 24) + 5, u32::MAX]);
+
+        assert!(vert_indices_from_f32_pair(&[16_777_216.0], &[0.0]).is_err());
+        assert!(vert_indices_from_f32_pair(&[0.0], &[256.0]).is_err());
+        assert!(vert_indices_from_f32_pair(&[0.0, 1.0], &[0.0]).is_err());
+    }
+
+    #[test]
+    fn zero_is_unset_in_current_exports() {
+        let current = VertIndexMap::from_indices(&[0, 1, 2, 0], true);
+        assert_eq!(current.get(0), None);
+        assert_eq!(current.get(2), Some(2));
+
+        let legacy = VertIndexMap::from_indices(&[0, 1, 2, 0], false);
+        assert_eq!(legacy.get(0), Some(0));
+    }
+
+    #[test]
+    fn edges_resolve_against_their_own_layer() {
+        // vertices 0-1 came from the first object, 2-3 from one joined into it
+        let layers = VertIndexLayers {
+            layers: vec![
+                (0, VertIndexMap::from_indices(&[1, 2, 0, 0], true)),
+                (1, VertIndexMap::from_indices(&[0, 0, 1, 2], true)),
+            ],
+        };
+        assert_eq!(layers.resolve_edge([1, 2], Some(0)), Some((0, 1)));
+        assert_eq!(layers.resolve_edge([1, 2], Some(1)), Some((2, 3)));
+        // unlayered edges only use _VERT_INDEX
+        assert_eq!(layers.resolve_edge([1, 2], None), Some((0, 1)));
+        // a missing layer resolves nothing rather than falling back to another one
+        assert_eq!(layers.resolve_edge([1, 2], Some(2)), None);
+        assert_eq!(layers.resolve_edge([1, 3], Some(0)), None);
+    }
+
+    #[test]
+    fn seeded_colors_are_pinned() {
+        // the first and last vertex are welded and share a color
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]],
+        );
+        colorize_vertex_colors(&mut mesh, &ColorizeStrategy::RandomPerVertex, 42).unwrap();
+
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR) else {
+            panic!("no colors");
+        };
+        let expected = vec![
+            [0.22408074, 0.68189615, 0.1463862, 1.0],
+            [0.95027536, 0.7723133, 0.4275164, 1.0],
+            [0.34409183, 0.62736046, 0.73715603, 1.0],
+            [0.22408074, 0.68189615, 0.1463862, 1.0],
+        ];
+        assert_eq!(colors, &expected);
+    }
+
+    #[test]
+    fn line_lods_keep_primary_and_drop_detail_edges() {
+        // a flat quad, its diagonal has a 0 degree crease and the sides are boundaries
+        let corners = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, corners.to_vec());
+        mesh.insert_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]));
+
+        let line = |a: usize, b: usize, tier| Line {
+            start: Vert {
+                position: corners[a],
+                ..Default::default()
+            },
+            end: Vert {
+                position: corners[b],
+                ..Default::default()
+            },
+            tier,
+            color: None,
+        };
+        let line_list = LineList {
+            lines: vec![
+                line(0, 1, EdgeTier::Secondary),
+                line(0, 2, EdgeTier::Secondary),
+                line(0, 2, EdgeTier::Primary),
+                line(2, 3, EdgeTier::Detail),
+            ],
+        };
+
+        let lods = line_list_lods(&line_list, &mesh, &LINE_LOD_CREASE_ANGLES);
+        // the 60 degree LOD would drop nothing more and is skipped
+        assert_eq!(lods.len(), 2);
+        assert_eq!(lods[0].lines.len(), 4);
+        let tiers: Vec<EdgeTier> = lods[1].lines.iter().map(|line| line.tier).collect();
+        assert_eq!(tiers, vec![EdgeTier::Secondary, EdgeTier::Primary]);
+        assert_eq!(lods[1].lines[1].start.position, corners[0]);
+        assert_eq!(lods[1].lines[1].end.position, corners[2]);
+    }
+
+    #[test]
+    fn index_layer_names() {
+        assert_eq!(vert_index_layer_from_name("_VERT_INDEX"), Some(0));
+        assert_eq!(vert_index_layer_from_name("_VERT_INDEX.003"), Some(3));
+        assert_eq!(vert_index_layer_from_name("VERT_INDEX_U32.002"), Some(2));
+        assert_eq!(vert_index_layer_from_name("_VERT_INDEX.008"), None);
+        assert_eq!(vert_index_layer_from_name("_VERT_INDEX_HI"), None);
+        assert_eq!(vert_index_hi_layer_from_name("_VERT_INDEX_HI.001"), Some(1));
+        assert_eq!(vert_index_hi_layer_from_name("_VERT_INDEX"), None);
+    }
+}
@@ -8,6 +8,7 @@ use bevy::render::render_resource::{
 
 // use crate::{ATTRIBUTE_ALT_COLOR};
 use crate::{ATTRIBUTE_SMOOTHED_NORMAL};
+use crate::fog::{FADE_TO_FOG_COLOR, FADE_TO_TRANSPARENT};
 
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 pub struct OutlineMaterial {
@@ -21,11 +22,24 @@ pub struct OutlineMaterial {
     pub vertex_color_mode: i32,
     #[uniform(0)]
     pub brightness: f32,
+    // 0..1, below 1 the layer is drawn with blending
     #[uniform(0)]
-    pub visibility: f32,
+    pub opacity: f32,
+    // fog::FADE_TO_FOG_COLOR or fog::FADE_TO_TRANSPARENT
+    #[uniform(0)]
+    pub fog_mode: i32,
 }
 
 impl Material for OutlineMaterial {
+    // see-through layers and fading to transparent need blending, everything else stays opaque
+    fn alpha_mode(&self) -> AlphaMode {
+        if self.opacity < 1.0 || self.fog_mode == FADE_TO_TRANSPARENT {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        }
+    }
+
     fn vertex_shader() -> ShaderRef {
         "shaders/outline.wgsl".into()
     }
@@ -70,7 +84,8 @@ impl Default for OutlineMaterial {
             z_translate: 0.1,
             vertex_color_mode: 0,
             brightness: 15.0,
-            visibility: 1.0,
+            opacity: 1.0,
+            fog_mode: FADE_TO_FOG_COLOR,
         }
     }
 }
@@ -2,10 +2,137 @@
 use serde_json::Value;
 use std::collections::HashMap;
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, Debug, Default)]
 pub struct JsonLineList {
-    #[serde(rename = "visibleEdges")]
+    #[serde(flatten)]
+    pub edges: JsonEdgeSet,
+    // primitives joined from several objects carry one edge set per `_VERT_INDEX.00N` layer
+    #[serde(rename = "edgeSets", default)]
+    pub edge_sets: Vec<JsonEdgeSet>,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct JsonEdgeSet {
+    #[serde(rename = "visibleEdges", default)]
     pub line_list: Vec<[u32; 2]>,
+    // 0 refers to `_VERT_INDEX`, N to `_VERT_INDEX.00N`. Legacy data without a layer
+    // only resolves against `_VERT_INDEX`
+    #[serde(rename = "indexLayer", default)]
+    pub index_layer: Option<usize>,
+    // edges the artist tagged by importance, `visibleEdges` are secondary
+    #[serde(rename = "edgeGroups", default)]
+    pub groups: Vec<JsonEdgeGroup>,
+    // line colors for `visibleEdges`, per edge or one for all of them
+    #[serde(rename = "edgeColors", default)]
+    pub colors: Vec<Option<[f32; 4]>>,
+    #[serde(rename = "edgeColor", default)]
+    pub color: Option<[f32; 4]>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct JsonEdgeGroup {
+    pub tier: EdgeTier,
+    pub edges: Vec<[u32; 2]>,
+    // per edge colors win over the group's
+    #[serde(default)]
+    pub colors: Vec<Option<[f32; 4]>>,
+    #[serde(default)]
+    pub color: Option<[f32; 4]>,
+}
+
+/// An edge from the extras, `color` is linear RGBA like `COLOR_0` and replaces the vertex colors
+/// of the line when set.
+#[derive(Clone, Copy, Debug)]
+pub struct JsonEdge {
+    pub vertices: [u32; 2],
+    pub tier: EdgeTier,
+    pub color: Option<[f32; 4]>,
+}
+
+/// How important an edge is to the look of a model, the wireframe styles and LODs each tier
+/// separately. Untagged and generated edges are secondary.
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeTier {
+    Primary,
+    #[default]
+    Secondary,
+    Detail,
+}
+
+impl EdgeTier {
+    pub const ALL: [EdgeTier; 3] = [EdgeTier::Primary, EdgeTier::Secondary, EdgeTier::Detail];
+
+    // the value of ATTRIBUTE_EDGE_TIER without flags, keep in sync with shaders/line.wgsl and
+    // shaders/instanced.wgsl
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+}
+
+impl JsonLineList {
+    pub fn edge_sets(&self) -> impl Iterator<Item = &JsonEdgeSet> {
+        std::iter::once(&self.edges).chain(self.edge_sets.iter())
+    }
+}
+
+impl JsonEdgeSet {
+    /// Every edge of the set with its tier and color, tagged groups first so they win over
+    /// `visibleEdges`.
+    pub fn tagged_edges(&self) -> impl Iterator<Item = JsonEdge> + '_ {
+        let grouped = self.groups.iter().flat_map(|group| {
+            edges_with_colors(&group.edges, &group.colors, group.color, group.tier)
+        });
+        grouped.chain(edges_with_colors(
+            &self.line_list,
+            &self.colors,
+            self.color,
+            EdgeTier::Secondary,
+        ))
+    }
+
+    /// Per edge color lists that don't line up with their edges. Extra colors are dropped and
+    /// edges past the end fall back to the list's single color, which is rarely what was meant.
+    pub fn color_mismatches(&self) -> Vec<String> {
+        let lists = self
+            .groups
+            .iter()
+            .map(|group| (format!("{:?} edgeGroup", group.tier), &group.edges, &group.colors))
+            .chain(std::iter::once(("visibleEdges".to_string(), &self.line_list, &self.colors)));
+
+        lists
+            .filter(|(_, edges, colors)| !colors.is_empty() && colors.len() != edges.len())
+            .map(|(name, edges, colors)| format!("{} has {} colors for {} edges", name, colors.len(), edges.len()))
+            .collect()
+    }
+}
+
+fn edges_with_colors<'a>(
+    edges: &'a [[u32; 2]],
+    colors: &'a [Option<[f32; 4]>],
+    color: Option<[f32; 4]>,
+    tier: EdgeTier,
+) -> impl Iterator<Item = JsonEdge> + 'a {
+    edges.iter().enumerate().map(move |(index, &vertices)| JsonEdge {
+        vertices,
+        tier,
+        color: colors.get(index).copied().flatten().or(color),
+    })
+}
+
+impl From<Vec<[u32; 2]>> for JsonLineList {
+    fn from(line_list: Vec<[u32; 2]>) -> Self {
+        JsonLineList {
+            edges: JsonEdgeSet {
+                line_list,
+                index_layer: None,
+                groups: Vec::new(),
+                colors: Vec::new(),
+                color: None,
+            },
+            edge_sets: Vec::new(),
+        }
+    }
 }
 
 // pub fn parse_gltf_extra_json(json_str: &str) -> Option<HashMap<String, JsonLineList>> {
@@ -49,17 +176,33 @@ pub struct JsonLineList {
 //         })
 // }
 
+/// Reads the edge list Blender stores as a JSON string under `gltf_primitive_extras` in a node's extras.
+pub fn line_list_from_node_extras(extras: &str) -> Option<JsonLineList> {
+    let json_value = serde_json::from_str::<Value>(extras).ok()?;
+    let edge_data = json_value.get("gltf_primitive_extras")?;
+    serde_json::from_str::<JsonLineList>(edge_data.as_str().unwrap_or("")).ok()
+}
+
 pub fn parse_selected_edges(
     json_str: &str,
+) -> Result<HashMap<String, JsonLineList>, serde_json::Error> {
+    parse_scene_edges(json_str, "gltf_all_selected_edges")
+}
+
+/// Reads one of the older scene level edge maps (`gltf_all_selected_edges`, `gltf_visible_edges`),
+/// keyed by the `gltf_primitive_index` of the node they belong to.
+pub fn parse_scene_edges(
+    json_str: &str,
+    key: &str,
 ) -> Result<HashMap<String, JsonLineList>, serde_json::Error> {
     let parsed: Value = serde_json::from_str(json_str)?;
     let mut result = HashMap::new();
 
     if let Value::Object(obj) = parsed {
-        if let Some(Value::String(edges_str)) = obj.get("gltf_all_selected_edges") {
+        if let Some(Value::String(edges_str)) = obj.get(key) {
             let edges: HashMap<String, Vec<[u32; 2]>> = serde_json::from_str(edges_str)?;
             for (key, value) in edges {
-                result.insert(key, JsonLineList { line_list: value });
+                result.insert(key, JsonLineList::from(value));
             }
         }
     }
@@ -69,12 +212,73 @@ pub fn parse_selected_edges(
 
 impl From<Vec<Vec<i32>>> for JsonLineList {
     fn from(edges: Vec<Vec<i32>>) -> Self {
-        let line_list = edges
+        let line_list: Vec<[u32; 2]> = edges
             .into_iter()
             .map(|e| [e[0] as u32, e[1] as u32])
             .collect();
-        JsonLineList { line_list }
+        JsonLineList::from(line_list)
     }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge_set(json: &str) -> JsonEdgeSet {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn tagged_edges_put_groups_first_and_fall_back_to_the_single_color() {
+        let set = edge_set(
+            r#"{
+                "visibleEdges": [[1, 2], [2, 3]],
+                "edgeColors": [[1, 0, 0, 1]],
+                "edgeColor": [0, 0, 1, 1],
+                "edgeGroups": [
+                    {"tier": "primary", "edges": [[3, 4]], "color": [0, 1, 0, 1]},
+                    {"tier": "detail", "edges": [[4, 5], [5, 6]], "colors": [null, [1, 1, 1, 0]]}
+                ]
+            }"#,
+        );
+
+        let edges: Vec<([u32; 2], EdgeTier, Option<[f32; 4]>)> =
+            set.tagged_edges().map(|edge| (edge.vertices, edge.tier, edge.color)).collect();
+        assert_eq!(
+            edges,
+            vec![
+                ([3, 4], EdgeTier::Primary, Some([0.0, 1.0, 0.0, 1.0])),
+                ([4, 5], EdgeTier::Detail, None),
+                ([5, 6], EdgeTier::Detail, Some([1.0, 1.0, 1.0, 0.0])),
+                ([1, 2], EdgeTier::Secondary, Some([1.0, 0.0, 0.0, 1.0])),
+                ([2, 3], EdgeTier::Secondary, Some([0.0, 0.0, 1.0, 1.0])),
+            ]
+        );
+    }
+
+    #[test]
+    fn color_mismatches_name_the_lists_that_dont_line_up() {
+        let set = edge_set(
+            r#"{
+                "visibleEdges": [[1, 2], [2, 3]],
+                "edgeColors": [[1, 0, 0, 1]],
+                "edgeGroups": [
+                    {"tier": "primary", "edges": [[3, 4]], "colors": [[0, 1, 0, 1], null]},
+                    {"tier": "detail", "edges": [[4, 5]], "colors": [null]},
+                    {"tier": "secondary", "edges": [[5, 6]]}
+                ]
+            }"#,
+        );
+
+        assert_eq!(
+            set.color_mismatches(),
+            vec![
+                "Primary edgeGroup has 2 colors for 1 edges".to_string(),
+                "visibleEdges has 1 colors for 2 edges".to_string(),
+            ]
+        );
+        assert!(edge_set(r#"{"visibleEdges": [[1, 2]]}"#).color_mismatches().is_empty());
+    }
+}
@@ -0,0 +1,460 @@
+use bevy::{
+    core_pipeline::{bloom::BloomCompositeMode, tonemapping::Tonemapping},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::backdrop::SkySettings;
+use crate::camera_plugin::GlowSettings;
+use crate::ShaderSettings;
+
+// Named looks: shader settings, glow, sky and background together. A few are built in, the rest are
+// saved by the user as RON, one file per preset in `presets/` natively and in localStorage on the web.
+
+pub struct PresetsPlugin;
+
+impl Plugin for PresetsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut presets = builtin_presets();
+        for preset in load_user_presets() {
+            match name_conflict(&presets, &preset.name) {
+                Some(conflict) => warn!("skipping preset {}: {}", preset.name, conflict),
+                None => {
+                    upsert(&mut presets, preset);
+                }
+            }
+        }
+        app.insert_resource(LookPresets {
+            presets,
+            selected: None,
+            new_name: String::new(),
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LookPreset {
+    pub name: String,
+    pub shader: ShaderSettings,
+    pub glow: GlowSettings,
+    // presets saved before the sky existed get the default one
+    #[serde(default)]
+    pub sky: SkySettings,
+    // shows when the sky is off
+    #[serde(with = "color_srgba")]
+    pub background: Color,
+    #[serde(skip)]
+    pub builtin: bool,
+    // listed by the look config, replaced whenever it reloads
+    #[serde(skip)]
+    pub from_config: bool,
+}
+
+#[derive(Resource)]
+pub struct LookPresets {
+    pub presets: Vec<LookPreset>,
+    pub selected: Option<usize>,
+    new_name: String,
+}
+
+impl LookPresets {
+    /// Adds the preset, replacing one with the same name. Returns its index.
+    pub fn upsert(&mut self, preset: LookPreset) -> usize {
+        upsert(&mut self.presets, preset)
+    }
+
+    /// Replaces the styles of the previous look config with these, they can't be saved over or deleted
+    /// like built-ins. Keeps the selection if its preset is still there.
+    pub fn set_config_styles(&mut self, styles: &[LookPreset]) {
+        let selected = self
+            .selected
+            .and_then(|index| self.presets.get(index))
+            .map(|preset| preset.name.clone());
+
+        self.presets.retain(|preset| !preset.from_config);
+        for style in styles {
+            let key = preset_key(&style.name);
+            if let Some(existing) = self.presets.iter().find(|p| preset_key(&p.name) == key) {
+                warn!("skipping look config style {}: clashes with {}", style.name, existing.name);
+                continue;
+            }
+            self.presets.push(LookPreset {
+                builtin: true,
+                from_config: true,
+                ..style.clone()
+            });
+        }
+
+        self.selected = selected.and_then(|name| self.presets.iter().position(|preset| preset.name == name));
+    }
+}
+
+// the file name a preset is saved under
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+// Names are compared by the file they'd be saved to, ignoring case for case insensitive file
+// systems, so "Neon Glow" and "neon_glow" can't both exist. Built-ins have no file, but taking
+// one's name would shadow it.
+fn preset_key(name: &str) -> String {
+    sanitize_name(name).to_lowercase()
+}
+
+// Why `name` can't be saved next to `presets`, overwriting a user preset of the exact same name is fine
+fn name_conflict(presets: &[LookPreset], name: &str) -> Option<String> {
+    let key = preset_key(name);
+    let existing = presets.iter().find(|p| preset_key(&p.name) == key && (p.builtin || p.name != name))?;
+    Some(if existing.builtin {
+        format!("{} is a built-in preset", existing.name)
+    } else {
+        format!("clashes with {}", existing.name)
+    })
+}
+
+fn upsert(presets: &mut Vec<LookPreset>, preset: LookPreset) -> usize {
+    match presets.iter().position(|p| p.name == preset.name) {
+        Some(index) => {
+            presets[index] = preset;
+            index
+        }
+        None => {
+            presets.push(preset);
+            presets.len() - 1
+        }
+    }
+}
+
+fn builtin_presets() -> Vec<LookPreset> {
+    let preset = |name: &str,
+                  shader: ShaderSettings,
+                  glow: GlowSettings,
+                  sky: SkySettings,
+                  background: Color| LookPreset {
+        name: name.to_string(),
+        shader,
+        glow,
+        sky,
+        background,
+        builtin: true,
+        from_config: false,
+    };
+
+    vec![
+        preset(
+            "Default",
+            ShaderSettings::default(),
+            GlowSettings::default(),
+            SkySettings::default(),
+            Color::BLACK,
+        ),
+        preset(
+            "Outrun",
+            ShaderSettings {
+                brightness: 20.0,
+                vertex_color_mode: 0,
+                color: Color::srgb(1.0, 0.2, 0.8),
+                ..default()
+            },
+            GlowSettings {
+                intensity: 0.3,
+                low_frequency_boost: 0.8,
+                ..default()
+            },
+            SkySettings {
+                sun_brightness: 6.0,
+                sun_size: 16.0,
+                sun_stripes: 9,
+                ..default()
+            },
+            Color::srgb(0.05, 0.0, 0.1),
+        ),
+        preset(
+            "Tron",
+            ShaderSettings {
+                outline_width: 0.05,
+                fill_shininess: 200.0,
+                vertex_color_mode: 0,
+                color: Color::srgb(0.0, 0.9, 1.0),
+                ..default()
+            },
+            GlowSettings {
+                intensity: 0.25,
+                composite_mode: BloomCompositeMode::Additive,
+                ..default()
+            },
+            SkySettings {
+                top: Color::BLACK,
+                horizon: Color::srgb(0.0, 0.15, 0.25),
+                bottom: Color::BLACK,
+                sun_size: 0.0,
+                star_density: 0.0,
+                ..default()
+            },
+            Color::BLACK,
+        ),
+        preset(
+            "Monochrome terminal",
+            ShaderSettings {
+                brightness: 10.0,
+                vertex_color_mode: 0,
+                color: Color::srgb(0.2, 1.0, 0.3),
+                show_outline: false,
+                show_fill: false,
+                ..default()
+            },
+            GlowSettings {
+                intensity: 0.2,
+                tonemapping: Tonemapping::AgX,
+                ..default()
+            },
+            SkySettings {
+                enabled: false,
+                ..default()
+            },
+            Color::srgb(0.0, 0.02, 0.0),
+        ),
+    ]
+}
+
+/// Preset dropdown and save/delete controls for the controls window.
+#[derive(SystemParam)]
+pub struct PresetEditor<'w> {
+    presets: ResMut<'w, LookPresets>,
+    sky: ResMut<'w, SkySettings>,
+    clear_color: ResMut<'w, ClearColor>,
+}
+
+impl PresetEditor<'_> {
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        shader_settings: &mut ShaderSettings,
+        glow: &mut ResMut<GlowSettings>,
+    ) {
+        ui.heading("Presets");
+
+        let presets = &mut *self.presets;
+        let selected_name = presets
+            .selected
+            .and_then(|index| presets.presets.get(index))
+            .map_or("Custom", |preset| preset.name.as_str())
+            .to_string();
+
+        let mut selected = presets.selected;
+        egui::ComboBox::from_label("Preset")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for (index, preset) in presets.presets.iter().enumerate() {
+                    ui.selectable_value(&mut selected, Some(index), &preset.name);
+                }
+            });
+
+        if selected != presets.selected {
+            presets.selected = selected;
+            if let Some(preset) = selected.and_then(|index| presets.presets.get(index)) {
+                *shader_settings = preset.shader.clone();
+                glow.set_if_neq(preset.glow);
+                self.sky.set_if_neq(preset.sky);
+                self.clear_color.0 = preset.background;
+            }
+        }
+
+        let name = presets.new_name.trim().to_string();
+        let conflict = name_conflict(&presets.presets, &name);
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut presets.new_name);
+            if ui.button("Save").clicked() && !name.is_empty() && conflict.is_none() {
+                let preset = LookPreset {
+                    name,
+                    shader: shader_settings.clone(),
+                    glow: **glow,
+                    sky: *self.sky,
+                    background: self.clear_color.0,
+                    builtin: false,
+                    from_config: false,
+                };
+                match save_user_preset(&preset) {
+                    Ok(()) => presets.selected = Some(presets.upsert(preset)),
+                    Err(e) => warn!("unable to save preset {}: {}", preset.name, e),
+                }
+            }
+        });
+        if let Some(conflict) = conflict {
+            ui.label(format!("Can't save, {}", conflict));
+        }
+
+        let deletable = presets
+            .selected
+            .and_then(|index| presets.presets.get(index))
+            .filter(|preset| !preset.builtin)
+            .map(|preset| preset.name.clone());
+        if let Some(name) = deletable {
+            if ui.button(format!("Delete {}", name)).clicked() {
+                match delete_user_preset(&name) {
+                    Ok(()) => {
+                        presets.presets.retain(|preset| preset.name != name);
+                        presets.selected = None;
+                    }
+                    Err(e) => warn!("unable to delete preset {}: {}", name, e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const PRESET_DIR: &str = "presets";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn preset_file(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(PRESET_DIR).join(format!("{}.ron", sanitize_name(name)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_user_presets() -> Vec<LookPreset> {
+    let Ok(entries) = std::fs::read_dir(PRESET_DIR) else {
+        return Vec::new();
+    };
+
+    let mut presets = Vec::new();
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.extension().and_then(|e| e.to_str()) != Some("ron") {
+            continue;
+        }
+        let preset = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|ron| Ok(ron::from_str::<LookPreset>(&ron)?));
+        match preset {
+            Ok(preset) => presets.push(preset),
+            Err(e) => warn!("unable to load preset {}: {}", path.display(), e),
+        }
+    }
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+    presets
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_user_preset(preset: &LookPreset) -> anyhow::Result<()> {
+    std::fs::create_dir_all(PRESET_DIR)?;
+    let ron = ron::ser::to_string_pretty(preset, ron::ser::PrettyConfig::default())?;
+    std::fs::write(preset_file(&preset.name), ron)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn delete_user_preset(name: &str) -> anyhow::Result<()> {
+    std::fs::remove_file(preset_file(name))?;
+    Ok(())
+}
+
+// the web build keeps every user preset in one localStorage entry
+#[cfg(target_arch = "wasm32")]
+const PRESET_STORAGE_KEY: &str = "bevy_vaporwave.presets";
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> anyhow::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| anyhow::anyhow!("localStorage is not available"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_user_presets() -> Vec<LookPreset> {
+    let stored = local_storage()
+        .ok()
+        .and_then(|storage| storage.get_item(PRESET_STORAGE_KEY).ok().flatten());
+    match stored.map(|ron| ron::from_str::<Vec<LookPreset>>(&ron)) {
+        Some(Ok(presets)) => presets,
+        Some(Err(e)) => {
+            warn!("unable to load presets from localStorage: {}", e);
+            Vec::new()
+        }
+        None => Vec::new(),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn store_user_presets(presets: &[LookPreset]) -> anyhow::Result<()> {
+    let ron = ron::to_string(presets)?;
+    local_storage()?
+        .set_item(PRESET_STORAGE_KEY, &ron)
+        .map_err(|_| anyhow::anyhow!("localStorage is full or disabled"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_user_preset(preset: &LookPreset) -> anyhow::Result<()> {
+    let mut presets = load_user_presets();
+    upsert(&mut presets, preset.clone());
+    store_user_presets(&presets)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn delete_user_preset(name: &str) -> anyhow::Result<()> {
+    let mut presets = load_user_presets();
+    presets.retain(|preset| preset.name != name);
+    store_user_presets(&presets)
+}
+
+// bevy's types only implement serde with its `serialize` feature, so presets store plain values
+
+pub mod color_srgba {
+    use bevy::color::{Color, ColorToComponents, Srgba};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        color.to_srgba().to_f32_array().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        Ok(Srgba::from_f32_array(<[f32; 4]>::deserialize(deserializer)?).into())
+    }
+}
+
+pub mod composite_mode {
+    use bevy::core_pipeline::bloom::BloomCompositeMode;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(mode: &BloomCompositeMode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", mode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BloomCompositeMode, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "EnergyConserving" => Ok(BloomCompositeMode::EnergyConserving),
+            "Additive" => Ok(BloomCompositeMode::Additive),
+            other => Err(D::Error::custom(format!("unknown bloom composite mode {}", other))),
+        }
+    }
+}
+
+pub mod tonemapping {
+    use bevy::core_pipeline::tonemapping::Tonemapping;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub const ALL: [Tonemapping; 8] = [
+        Tonemapping::None,
+        Tonemapping::Reinhard,
+        Tonemapping::ReinhardLuminance,
+        Tonemapping::AcesFitted,
+        Tonemapping::AgX,
+        Tonemapping::SomewhatBoringDisplayTransform,
+        Tonemapping::TonyMcMapface,
+        Tonemapping::BlenderFilmic,
+    ];
+
+    pub fn serialize<S: Serializer>(tonemapping: &Tonemapping, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", tonemapping))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Tonemapping, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        ALL.into_iter()
+            .find(|tonemapping| format!("{:?}", tonemapping) == name)
+            .ok_or_else(|| D::Error::custom(format!("unknown tonemapping {}", name)))
+    }
+}
@@ -52,7 +52,7 @@ for entity in children.iter_descendants(event.parent) {
     {
         if let Some(flat_mesh) = mesh_assets.get_mut(mesh_handle) {
             commands.entity(entity).remove::<Handle<StandardMaterial>>();
-            flat_mesh.randomize_vertex_colors();
+            flat_mesh.randomize_vertex_colors(0);
 
             let smoothed_normals = get_smoothed_normals(flat_mesh).unwrap();
             flat_mesh.insert_attribute(ATTRIBUTE_SMOOTHED_NORMAL, smoothed_normals);
@@ -0,0 +1,55 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypePath,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+// colors are linear, the sun ones already multiplied by its brightness
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone, Default)]
+pub struct SkyMaterial {
+    #[uniform(0)]
+    pub top: Vec4,
+    #[uniform(0)]
+    pub horizon: Vec4,
+    #[uniform(0)]
+    pub bottom: Vec4,
+    #[uniform(0)]
+    pub sun_top: Vec4,
+    #[uniform(0)]
+    pub sun_bottom: Vec4,
+    #[uniform(0)]
+    pub sun_direction: Vec3,
+    // angular radius in radians, 0 hides the sun
+    #[uniform(0)]
+    pub sun_size: f32,
+    #[uniform(0)]
+    pub sun_stripes: f32,
+    // fraction of star cells with a star in them
+    #[uniform(0)]
+    pub star_density: f32,
+    #[uniform(0)]
+    pub star_brightness: f32,
+}
+
+impl Material for SkyMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/sky.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // the camera sits inside the sky sphere
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+// Procedural mountain ranges for backdrops. A seeded fbm heightfield, flattened into a valley along
+// the z axis, comes out as a fill mesh plus the LineList of its grid edges, so the runtime can give
+// it the same fill, outline and wireframe layers as an imported model.
+
+use anyhow::Result;
+
+use bevy::{
+    math::{Vec2, Vec3},
+    prelude::Mesh,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+
+use crate::mesh_ops::{colorize_vertex_colors, AsFloat4, ColorizeStrategy, Line, LineList, Vert};
+use crate::ATTRIBUTE_SMOOTHED_NORMAL;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainSettings {
+    /// Extent along x and z, centred on the origin
+    pub size: Vec2,
+    /// Grid cells along x and z
+    pub resolution: [u32; 2],
+    /// Height of the tallest possible peak
+    pub height: f32,
+    /// Width of the flat strip along the z axis
+    pub valley_width: f32,
+    /// Rough distance between neighbouring peaks
+    pub feature_size: f32,
+    pub seed: u64,
+    /// A HeightGradient spans 0 to `height`
+    pub colors: ColorizeStrategy,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(120.0, 60.0),
+            resolution: [60, 30],
+            height: 12.0,
+            valley_width: 16.0,
+            feature_size: 14.0,
+            seed: 0,
+            colors: ColorizeStrategy::HeightGradient {
+                bottom: [0.3, 0.0, 0.5, 1.0],
+                top: [0.0, 0.9, 1.0, 1.0],
+            },
+        }
+    }
+}
+
+impl TerrainSettings {
+    /// Spacing of the grid lines along x and z.
+    pub fn cell_size(&self) -> Vec2 {
+        self.size / Vec2::new(self.resolution[0].max(1) as f32, self.resolution[1].max(1) as f32)
+    }
+
+    /// Height of the terrain at `x`, `z`, before any scrolling.
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let half_valley = self.valley_width * 0.5;
+        // the slopes rise over the inner 40% of each side
+        let rise = ((self.size.x * 0.5 - half_valley) * 0.4).max(f32::EPSILON);
+        let mask = smoothstep(half_valley, half_valley + rise, x.abs());
+
+        let p = Vec2::new(x, z) / self.feature_size.max(f32::EPSILON);
+        let n = fbm(p, self.seed);
+        // squaring sharpens the peaks and widens the foothills
+        n * n * self.height * mask
+    }
+}
+
+pub struct Terrain {
+    /// De-indexed triangles with flat normals and smoothed normals, ready for the fill and outline materials
+    pub mesh: Mesh,
+    /// Every grid edge, for `line_list_to_mesh`
+    pub line_list: LineList,
+}
+
+/// Builds the terrain. `scroll` moves the heightfield towards -z and should be a whole number of
+/// cells, for endless-runner scenes shift the entity by the remainder, see `TerrainSettings::cell_size`.
+pub fn generate_terrain(settings: &TerrainSettings, scroll: f32) -> Result<Terrain> {
+    let [cells_x, cells_z] = settings.resolution.map(|cells| cells.max(1) as usize);
+    let cell = settings.cell_size();
+    let origin = settings.size * -0.5;
+    let row = cells_x + 1;
+
+    let sample = |i: usize, j: usize| {
+        let x = origin.x + i as f32 * cell.x;
+        let z = origin.y + j as f32 * cell.y;
+        Vec3::new(x, settings.height_at(x, z + scroll), z)
+    };
+
+    let mut positions = Vec::with_capacity(row * (cells_z + 1));
+    for j in 0..=cells_z {
+        for i in 0..=cells_x {
+            positions.push(sample(i, j));
+        }
+    }
+
+    // central differences, one-sided on the border
+    let normals: Vec<[f32; 3]> = (0..positions.len())
+        .map(|index| {
+            let (i, j) = (index % row, index / row);
+            let dx = positions[j * row + (i + 1).min(cells_x)] - positions[j * row + i.saturating_sub(1)];
+            let dz = positions[(j + 1).min(cells_z) * row + i] - positions[j.saturating_sub(1) * row + i];
+            dz.cross(dx).normalize_or(Vec3::Y).to_array()
+        })
+        .collect();
+
+    let mut indices = Vec::with_capacity(cells_x * cells_z * 6);
+    for j in 0..cells_z {
+        for i in 0..cells_x {
+            let a = (j * row + i) as u32;
+            let b = a + 1;
+            let c = a + row as u32;
+            let d = c + 1;
+            indices.extend_from_slice(&[a, c, b, b, c, d]);
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions.iter().map(|p| p.to_array()).collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.clone());
+    mesh.insert_indices(Indices::U32(indices));
+    match settings.colors {
+        // against the full possible height rather than this patch's min/max like colorize does,
+        // so a peak keeps its color while the terrain scrolls
+        ColorizeStrategy::HeightGradient { bottom, top } => {
+            let colors: Vec<[f32; 4]> = positions
+                .iter()
+                .map(|p| {
+                    let t = (p.y / settings.height.max(f32::EPSILON)).clamp(0.0, 1.0);
+                    std::array::from_fn(|i| bottom[i] + (top[i] - bottom[i]) * t)
+                })
+                .collect();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        _ => colorize_vertex_colors(&mut mesh, &settings.colors, settings.seed)?,
+    }
+
+    let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR).and_then(|colors| colors.as_float4()) {
+        Some(colors) => colors,
+        None => vec![[1.0; 4]; positions.len()],
+    };
+    let vert = |index: usize| Vert {
+        position: positions[index].to_array(),
+        normal: normals[index],
+        color: Some(colors[index]),
+        ..Default::default()
+    };
+
+    let mut line_list = LineList::default();
+    for j in 0..=cells_z {
+        for i in 0..=cells_x {
+            let index = j * row + i;
+            if i < cells_x {
+                line_list.lines.push(Line::new(vert(index), vert(index + 1)));
+            }
+            if j < cells_z {
+                line_list.lines.push(Line::new(vert(index), vert(index + row)));
+            }
+        }
+    }
+
+    // same layout prepare_vaporwave_mesh gives imported meshes
+    mesh.insert_attribute(ATTRIBUTE_SMOOTHED_NORMAL, normals);
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+
+    Ok(Terrain { mesh, line_list })
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// splitmix64 finaliser over the lattice point and seed, mapped to 0..1
+fn lattice_value(x: i32, z: i32, seed: u64) -> f32 {
+    let mut h = seed
+        ^ (x as u32 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (z as u32 as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn value_noise(p: Vec2, seed: u64) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let (x, z) = (cell.x as i32, cell.y as i32);
+    let u = f * f * (Vec2::splat(3.0) - 2.0 * f);
+
+    let a = lattice_value(x, z, seed);
+    let b = lattice_value(x + 1, z, seed);
+    let c = lattice_value(x, z + 1, seed);
+    let d = lattice_value(x + 1, z + 1, seed);
+    a + (b - a) * u.x + (c - a) * u.y + (a - b - c + d) * u.x * u.y
+}
+
+// four octaves of value noise, 0..1
+fn fbm(p: Vec2, seed: u64) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..4 {
+        sum += value_noise(p * frequency, seed.wrapping_add(octave)) * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / 0.9375
+}